@@ -1,4 +1,4 @@
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Type {
     Integer,
     Float,
@@ -6,6 +6,13 @@ pub enum Type {
     String,
     Empty,
     Never,
+    /// An array where every element has the given type
+    Array(Box<Type>),
+    /// A user defined struct. After static analysis the namespace is always absolute.
+    Struct {
+        namespace: Vec<String>,
+        identifier: String,
+    },
 }
 
 impl std::fmt::Display for Type {
@@ -17,6 +24,17 @@ impl std::fmt::Display for Type {
             Type::String => write!(f, "string"),
             Type::Empty => write!(f, "empty"),
             Type::Never => write!(f, "never"),
+            Type::Array(ty) => write!(f, "[{}]", ty),
+            Type::Struct {
+                namespace,
+                identifier,
+            } => {
+                if !namespace.is_empty() {
+                    write!(f, "{}::{}", namespace.join("::"), identifier)
+                } else {
+                    write!(f, "{}", identifier)
+                }
+            }
         }
     }
 }
@@ -1,14 +1,16 @@
 use super::*;
 
 pub fn compile(
-    nodes: &Vec<Node<parser::node::block::All<Node<parser::node::expression::AllWithType>>>>,
+    nodes: &parser::node::Block<parser::node::expression::AllWithType>,
+    source_map: &span::SourceMap,
 ) -> String {
     let mut code = String::new();
 
     for node in nodes {
         match &node.node {
             parser::node::block::All::Expression { value, .. } => {
-                code.push_str(&expression::compile(&value));
+                code.push_str(&expression::compile(value, source_map));
+                code.push(';');
             }
             parser::node::block::All::VariableDeclaration {
                 identifier, value, ..
@@ -16,15 +18,148 @@ pub fn compile(
                 code.push_str(
                     format!(
                         "let {} = {{ value: {} }};",
-                        get_var_name(vec![], &identifier),
-                        expression::compile(&value)
+                        get_var_name(&[], identifier),
+                        compile_value(value, source_map)
                     )
                     .as_str(),
                 );
             }
-            _ => todo!(),
+            parser::node::block::All::ConstantDeclaration {
+                identifier, value, ..
+            } => {
+                code.push_str(&format!(
+                    "const {} = {{ value: {} }};",
+                    get_var_name(&[], identifier),
+                    compile_value(value, source_map)
+                ));
+            }
+            parser::node::block::All::VariableAssignment {
+                target,
+                operator,
+                value,
+            } => {
+                // The JavaScript operator the compound assignment applies, its operands are
+                // always integers, floats or strings so they don't have to be copied
+                let operator = operator.as_ref().map(|operator| match operator {
+                    lexer::TokenType::Add => "+",
+                    lexer::TokenType::Sub => "-",
+                    lexer::TokenType::Mul => "*",
+                    lexer::TokenType::Div => "/",
+                    lexer::TokenType::Mod => "%",
+                    lexer::TokenType::Pow => "**",
+                    _ => unreachable!("Compound assignments only have arithmetic operators"),
+                });
+
+                match (&target.node, operator) {
+                    // Elements are set through the runtime so that the index is bounds checked
+                    (
+                        parser::node::expression::AllWithType::SingleDataUnit {
+                            value:
+                                parser::node::expression::SingleDataUnit::ArrayAccess { array, index },
+                            ..
+                        },
+                        operator,
+                    ) => {
+                        let value = match operator {
+                            // The array and the index are only evaluated once, the runtime passes
+                            // the current element to the function
+                            Some(operator) => format!(
+                                "(__zyrahn_element) => (__zyrahn_element) {} ({})",
+                                operator,
+                                expression::compile(value, source_map)
+                            ),
+                            None => compile_value(value, source_map),
+                        };
+
+                        code.push_str(&format!(
+                            "{}({}, {}, {}, {});",
+                            if operator.is_some() {
+                                "__zyrahn_update"
+                            } else {
+                                "__zyrahn_set"
+                            },
+                            expression::compile(array, source_map),
+                            expression::compile(index, source_map),
+                            value,
+                            get_location(target, source_map)
+                        ));
+                    }
+                    // JavaScript's own compound assignments only evaluate the target once
+                    (_, Some(operator)) => {
+                        code.push_str(&format!(
+                            "{} {}= {};",
+                            expression::compile(target, source_map),
+                            operator,
+                            expression::compile(value, source_map)
+                        ));
+                    }
+                    (_, None) => {
+                        code.push_str(&format!(
+                            "{} = {};",
+                            expression::compile(target, source_map),
+                            compile_value(value, source_map)
+                        ));
+                    }
+                }
+            }
+            parser::node::block::All::If { .. } | parser::node::block::All::IfElse { .. } => {
+                // The `else if` branches are compiled in a loop
+                let mut branch = node;
+
+                loop {
+                    let (cond, then_body, else_body) = match &branch.node {
+                        parser::node::block::All::If { cond, then_body } => (cond, then_body, None),
+                        parser::node::block::All::IfElse {
+                            cond,
+                            then_body,
+                            else_body,
+                        } => (cond, then_body, Some(else_body)),
+                        _ => unreachable!("An else if is always an if statement"),
+                    };
+
+                    code.push_str(&format!(
+                        "if ({}) {{{}}}",
+                        expression::compile(cond, source_map),
+                        compile(then_body, source_map)
+                    ));
+
+                    let Some(else_body) = else_body else {
+                        break;
+                    };
+
+                    match parser::node::block::else_if(else_body) {
+                        Some(else_if) => {
+                            code.push_str(" else ");
+                            branch = else_if;
+                        }
+                        None => {
+                            code.push_str(&format!(" else {{{}}}", compile(else_body, source_map)));
+                            break;
+                        }
+                    }
+                }
+            }
+            parser::node::block::All::While { cond, body } => {
+                code.push_str(&format!(
+                    "while ({}) {{{}}}",
+                    expression::compile(cond, source_map),
+                    compile(body, source_map)
+                ));
+            }
+            parser::node::block::All::Return { value: None } => code.push_str("return;"),
+            parser::node::block::All::Return { value: Some(value) } => {
+                code.push_str(&format!(
+                    "return {};",
+                    expression::compile(value, source_map)
+                ));
+            }
+            parser::node::block::All::Break {} => code.push_str("break;"),
+            parser::node::block::All::Continue {} => code.push_str("continue;"),
+            parser::node::block::All::Error {} => {
+                unreachable!("Code with syntax errors is never compiled")
+            }
         };
     }
 
-    return code;
+    code
 }
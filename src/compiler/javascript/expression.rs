@@ -1,75 +1,95 @@
 use super::*;
 use parser::node::expression;
 
-pub fn compile(node: &Node<expression::AllWithType>) -> String {
-    let node = &node.node;
-    match node {
+pub fn compile(node: &Node<expression::AllWithType>, source_map: &span::SourceMap) -> String {
+    // Binary operators nest to the left, so a long chain of them is walked in a loop instead of
+    // recursing into every left operand, the same as in the static analyzer
+    let mut chain = vec![];
+    let mut leftmost = node;
+    while let Some((left, operator, right)) = binary_operation(leftmost) {
+        chain.push((operator, right));
+        leftmost = left;
+    }
+
+    // Every operation is `(left) operator (right)`, the opening parentheses of all of them come
+    // first so that the code is only ever appended to
+    let mut code = "(".repeat(chain.len());
+    code.push_str(&compile_node(leftmost, source_map));
+    for (operator, right) in chain.into_iter().rev() {
+        code.push_str(") ");
+        code.push_str(operator);
+        code.push_str(" (");
+        code.push_str(&compile(right, source_map));
+        code.push(')');
+    }
+
+    code
+}
+
+/// Returns the operands and the JavaScript operator if the node is a binary operator
+fn binary_operation(
+    node: &Node<expression::AllWithType>,
+) -> Option<(
+    &Node<expression::AllWithType>,
+    &'static str,
+    &Node<expression::AllWithType>,
+)> {
+    let (left, operator, right) = match &node.node {
         expression::AllWithType::Cmp { value, .. } => match value {
-            expression::Cmp::Equal { left, right, .. } => {
-                format!("({}) == ({})", compile(left), compile(right))
-            }
-            expression::Cmp::NotEqual { left, right, .. } => {
-                format!("({}) != ({})", compile(left), compile(right))
-            }
-            expression::Cmp::LessThan { left, right, .. } => {
-                format!("({}) < ({})", compile(left), compile(right))
-            }
-            expression::Cmp::LessThanOrEqual { left, right, .. } => {
-                format!("({}) <= ({})", compile(left), compile(right))
-            }
-            expression::Cmp::GreaterThan { left, right, .. } => {
-                format!("({}) > {}", compile(left), compile(right))
-            }
-            expression::Cmp::GreaterThanOrEqual { left, right, .. } => {
-                format!("({}) >= ({})", compile(left), compile(right))
-            }
+            expression::Cmp::Equal { left, right } => (left, "==", right),
+            expression::Cmp::NotEqual { left, right } => (left, "!=", right),
+            expression::Cmp::LessThan { left, right } => (left, "<", right),
+            expression::Cmp::LessThanOrEqual { left, right } => (left, "<=", right),
+            expression::Cmp::GreaterThan { left, right } => (left, ">", right),
+            expression::Cmp::GreaterThanOrEqual { left, right } => (left, ">=", right),
         },
         expression::AllWithType::Arithmetic { value, .. } => match value {
-            expression::Arithmetic::Add { left, right, .. } => {
-                format!("({}) + ({})", compile(left), compile(right))
-            }
-            expression::Arithmetic::Sub { left, right, .. } => {
-                format!("({}) - ({})", compile(left), compile(right))
-            }
-            expression::Arithmetic::Mul { left, right, .. } => {
-                format!("({}) * ({})", compile(left), compile(right))
-            }
-            expression::Arithmetic::Div { left, right, .. } => {
-                format!("({}) / ({})", compile(left), compile(right))
-            }
-            expression::Arithmetic::Mod { left, right, .. } => {
-                format!("({}) % ({})", compile(left), compile(right))
-            }
-            expression::Arithmetic::Pow { left, right, .. } => {
-                format!("({}) ** ({})", compile(left), compile(right))
-            }
-            expression::Arithmetic::Neg { value, .. } => {
-                format!("-({})", compile(value))
-            }
+            expression::Arithmetic::Add { left, right } => (left, "+", right),
+            expression::Arithmetic::Sub { left, right } => (left, "-", right),
+            expression::Arithmetic::Mul { left, right } => (left, "*", right),
+            expression::Arithmetic::Div { left, right } => (left, "/", right),
+            expression::Arithmetic::Mod { left, right } => (left, "%", right),
+            expression::Arithmetic::Pow { left, right } => (left, "**", right),
+            expression::Arithmetic::Neg { .. } => return None,
         },
         expression::AllWithType::BooleanLogic { value, .. } => match value {
-            expression::BooleanLogic::Or { left, right, .. } => {
-                format!("({}) || ({})", compile(left), compile(right))
-            }
-            expression::BooleanLogic::And { left, right, .. } => {
-                format!("({}) && ({})", compile(left), compile(right))
-            }
-            expression::BooleanLogic::Not { value, .. } => {
-                format!("!({})", compile(value))
-            }
+            expression::BooleanLogic::Or { left, right } => (left, "||", right),
+            expression::BooleanLogic::And { left, right } => (left, "&&", right),
+            expression::BooleanLogic::Not { .. } => return None,
         },
+        expression::AllWithType::SingleDataUnit { .. } => return None,
+    };
+
+    Some((left, operator, right))
+}
+
+/// Compiles a node that isn't a binary operator
+fn compile_node(node: &Node<expression::AllWithType>, source_map: &span::SourceMap) -> String {
+    match &node.node {
+        expression::AllWithType::Arithmetic {
+            value: expression::Arithmetic::Neg { value },
+            ..
+        } => format!("-({})", compile(value, source_map)),
+        expression::AllWithType::BooleanLogic {
+            value: expression::BooleanLogic::Not { value },
+            ..
+        } => format!("!({})", compile(value, source_map)),
+        expression::AllWithType::Cmp { .. }
+        | expression::AllWithType::Arithmetic { .. }
+        | expression::AllWithType::BooleanLogic { .. } => {
+            unreachable!("Binary operators are compiled by `compile`")
+        }
         expression::AllWithType::SingleDataUnit { value, .. } => {
             match value {
                 expression::SingleDataUnit::Literal { literal, .. } => match literal {
                     expression::Literal::Float { value, .. } => {
                         format!("{}", value)
                     }
+                    // A BigInt literal, since going through a number loses precision above 2^53
                     expression::Literal::Integer { value, .. } => {
-                        format!("BigInt({})", value)
-                    }
-                    expression::Literal::String { value, .. } => {
-                        format!("\"{}\"", value)
+                        format!("{}n", value)
                     }
+                    expression::Literal::String { value, .. } => quote_string(value),
                     expression::Literal::Boolean { value, .. } => {
                         format!("{}", value)
                     }
@@ -79,9 +99,9 @@ pub fn compile(node: &Node<expression::AllWithType>) -> String {
                     identifier,
                     ..
                 } => {
-                    // Since a variable in zyrahn can't have numbers in them we can safetly combine
-                    // namespace like this
-                    format!("v{}__0{}.value", namespace.join("0"), identifier)
+                    // The namespace is already absolute, every segment of it is prefixed with its
+                    // length so that different paths can't mangle to the same name
+                    format!("{}.value", get_var_name(namespace, identifier))
                 }
                 expression::SingleDataUnit::FunctionCall {
                     function,
@@ -90,24 +110,38 @@ pub fn compile(node: &Node<expression::AllWithType>) -> String {
                 } => {
                     let name = match function.as_ref() {
                         Node {
-                            node: expression::AllWithType::SingleDataUnit { value, .. },
+                            node:
+                                expression::AllWithType::SingleDataUnit {
+                                    value:
+                                        expression::SingleDataUnit::Identifier {
+                                            namespace,
+                                            identifier,
+                                            ..
+                                        },
+                                    ..
+                                },
                             ..
-                        } => match value {
-                            expression::SingleDataUnit::Identifier {
-                                namespace,
-                                identifier,
-                                ..
-                            } => {
-                                let arg_types = arguments
-                                    .clone()
-                                    .into_iter()
-                                    .map(|(is_out, x)| (is_out, x.node.ty()))
+                        } => {
+                            let arg_types = arguments
+                                .iter()
+                                .map(|(is_out, x)| (*is_out, x.node.ty()))
+                                .collect::<Vec<_>>();
+
+                            if static_analyzer::built_in::get_function(
+                                namespace, identifier, &arg_types,
+                            )
+                            .is_some()
+                            {
+                                let arguments = arguments
+                                    .iter()
+                                    .map(|(_, x)| compile(x, source_map))
                                     .collect::<Vec<_>>();
 
-                                get_func_name(namespace, identifier, &arg_types)
+                                return std::compile_built_in(namespace, identifier, &arguments);
                             }
-                            _ => unreachable!(),
-                        },
+
+                            get_func_name(namespace, identifier, &arg_types)
+                        }
                         _ => unreachable!(),
                     };
 
@@ -121,36 +155,76 @@ pub fn compile(node: &Node<expression::AllWithType>) -> String {
                                     match x {
                                         Node {
                                             node:
-                                                expression::AllWithType::SingleDataUnit{ 
-                                                    value: expression::SingleDataUnit::Identifier {
-                                                        namespace,
-                                                        identifier,
-                                                        ..
-                                                    },
-                                                ..
+                                                expression::AllWithType::SingleDataUnit {
+                                                    value:
+                                                        expression::SingleDataUnit::Identifier {
+                                                            namespace,
+                                                            identifier,
+                                                            ..
+                                                        },
+                                                    ..
                                                 },
                                             ..
-                                        } => {
-                                            get_var_name(namespace, identifier)
-                                        }
+                                        } => get_var_name(namespace, identifier),
                                         _ => {
-                                            panic!("Static analyzer has given a tree that is not valid")
+                                            panic!(
+                                                "Static analyzer has given a tree that is not valid"
+                                            )
                                         }
                                     }
                                 } else {
-                                    format!("{{value:JSON.parse(JSON.stringify({}))}}", compile(x))
+                                    format!("{{ value: {} }}", compile_value(x, source_map))
                                 }
                             })
                             .collect::<Vec<String>>()
                             .join(", "),
                     )
                 }
-                expression::SingleDataUnit::ArrayInit { .. } => todo!(),
-                expression::SingleDataUnit::ArrayAccess { .. } => todo!(),
-                expression::SingleDataUnit::PropertyAccess { .. } => todo!(),
-                expression::SingleDataUnit::StructInit { .. } => todo!(),
+                expression::SingleDataUnit::ArrayInit { values } => {
+                    format!(
+                        "[{}]",
+                        values
+                            .iter()
+                            .map(|x| compile_value(x, source_map))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                }
+                expression::SingleDataUnit::ArrayAccess { array, index } => {
+                    format!(
+                        "__zyrahn_get({}, {}, {})",
+                        compile(array, source_map),
+                        compile(index, source_map),
+                        get_location(node, source_map)
+                    )
+                }
+                expression::SingleDataUnit::PropertyAccess { object, property } => {
+                    format!(
+                        "{}.{}",
+                        compile(object, source_map),
+                        get_field_name(property)
+                    )
+                }
+                expression::SingleDataUnit::StructInit { values, .. } => {
+                    // The fields are sorted so that the output doesn't depend on the order of
+                    // the hash map
+                    let mut fields = values.iter().collect::<Vec<_>>();
+                    fields.sort_by_key(|(field, _)| *field);
+
+                    format!(
+                        "{{{}}}",
+                        fields
+                            .iter()
+                            .map(|(field, value)| format!(
+                                "{}: {}",
+                                get_field_name(field),
+                                compile_value(value, source_map)
+                            ))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                }
             }
         }
-        _ => todo!(),
     }
 }
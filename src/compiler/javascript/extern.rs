@@ -0,0 +1,109 @@
+use super::*;
+
+/// Compiles an extern function. The function is imported from its module and wrapped in a
+/// function that takes arguments like any other zyrahn function, i.e. as `{ value }` objects with
+/// integers as `BigInt`, and converts them to plain JavaScript values and back.
+///
+/// # Example
+/// ```text
+/// extern fnc add(a: int, b: int) -> int = "./math.js#add";
+/// ```
+/// is compiled to
+/// ```js
+/// import { add as mf3add_ii } from "./math.js";
+/// function f3add_ii(v1a, v1b) {
+///     const __result = mf3add_ii(Number(v1a.value), Number(v1b.value));
+///     return BigInt(__result);
+/// }
+/// ```
+pub fn compile(
+    namespace: &[String],
+    identifier: &str,
+    arguments: &[parser::node::global::FunctionArgument],
+    return_type: &common::Type,
+    module: &str,
+    export: &str,
+) -> String {
+    let args = arguments
+        .iter()
+        .map(|arg| (arg.is_out, arg.ty.clone()))
+        .collect::<Vec<_>>();
+    let name = get_func_name(namespace, identifier, &args);
+    let import = format!("m{}", name);
+
+    let params = arguments
+        .iter()
+        .map(|arg| get_var_name(&[], &arg.identifier))
+        .collect::<Vec<_>>();
+
+    let mut body = String::new();
+    let mut call_args = vec![];
+
+    // Out arguments are passed as `{ value }` objects holding the converted value, which is
+    // converted back and written to the argument after the call
+    for (i, (arg, param)) in arguments.iter().zip(&params).enumerate() {
+        let value = to_js(&format!("{}.value", param), &arg.ty);
+
+        if arg.is_out {
+            body.push_str(&format!("const __out{} = {{ value: {} }};", i, value));
+            call_args.push(format!("__out{}", i));
+        } else {
+            call_args.push(value);
+        }
+    }
+
+    body.push_str(&format!(
+        "const __result = {}({});",
+        import,
+        call_args.join(", ")
+    ));
+
+    for (i, (arg, param)) in arguments.iter().zip(&params).enumerate() {
+        if arg.is_out {
+            body.push_str(&format!(
+                "{}.value = {};",
+                param,
+                from_js(&format!("__out{}.value", i), &arg.ty)
+            ));
+        }
+    }
+
+    if *return_type != common::Type::Empty {
+        body.push_str(&format!("return {};", from_js("__result", return_type)));
+    }
+
+    format!(
+        "import {{ {} as {} }} from {};function {}({}) {{{}}}",
+        export,
+        import,
+        quote_string(module),
+        name,
+        params.join(", "),
+        body
+    )
+}
+
+/// Converts a zyrahn value to a plain JavaScript value, i.e. turns integers into numbers
+fn to_js(value: &str, ty: &common::Type) -> String {
+    match ty {
+        common::Type::Integer => format!("Number({})", value),
+        common::Type::Array(ty) => match to_js("x", ty) {
+            // The elements don't need to be converted
+            element if element == "x" => value.to_string(),
+            element => format!("({}).map((x) => {})", value, element),
+        },
+        _ => value.to_string(),
+    }
+}
+
+/// Converts a plain JavaScript value to a zyrahn value, the reverse of `to_js`
+fn from_js(value: &str, ty: &common::Type) -> String {
+    match ty {
+        common::Type::Integer => format!("BigInt({})", value),
+        common::Type::Array(ty) => match from_js("x", ty) {
+            element if element == "x" => value.to_string(),
+            element => format!("({}).map((x) => {})", value, element),
+        },
+        _ => value.to_string(),
+    }
+}
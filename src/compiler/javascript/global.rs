@@ -0,0 +1,105 @@
+use super::*;
+
+pub fn compile(
+    nodes: &parser::node::Global<parser::node::expression::AllWithType>,
+    namespace: &[String],
+    source_map: &span::SourceMap,
+) -> String {
+    let mut code = String::new();
+
+    for node in nodes {
+        match &node.node {
+            parser::node::global::All::FunctionDeclaration {
+                identifier,
+                arguments,
+                body,
+                ..
+            } => {
+                let args = arguments
+                    .iter()
+                    .map(|arg| (arg.is_out, arg.ty.clone()))
+                    .collect::<Vec<_>>();
+
+                // Every argument is passed as a `{ value }` object, the same way variables are
+                // stored, so the body can use them like any other variable
+                let params = arguments
+                    .iter()
+                    .map(|arg| get_var_name(&[], &arg.identifier))
+                    .collect::<Vec<_>>();
+
+                code.push_str(&format!(
+                    "function {}({}) {{{}}}",
+                    get_func_name(namespace, identifier, &args),
+                    params.join(", "),
+                    block::compile(body, source_map)
+                ));
+            }
+            parser::node::global::All::IntrinsicDeclaration {
+                identifier,
+                arguments,
+                id,
+                ..
+            } => {
+                let args = arguments
+                    .iter()
+                    .map(|arg| (arg.is_out, arg.ty.clone()))
+                    .collect::<Vec<_>>();
+                let params = arguments
+                    .iter()
+                    .map(|arg| get_var_name(&[], &arg.identifier))
+                    .collect::<Vec<_>>();
+
+                let intrinsic = intrinsic::Intrinsic::from_id(id)
+                    .expect("Static analyzer has given a tree that is not valid");
+
+                code.push_str(&format!(
+                    "function {}({}) {{{}}}",
+                    get_func_name(namespace, identifier, &args),
+                    params.join(", "),
+                    std::compile_intrinsic(intrinsic, &params)
+                ));
+            }
+            parser::node::global::All::ExternDeclaration {
+                identifier,
+                arguments,
+                return_type,
+                module,
+                export,
+                ..
+            } => {
+                code.push_str(&r#extern::compile(
+                    namespace,
+                    identifier,
+                    arguments,
+                    return_type,
+                    module,
+                    export,
+                ));
+            }
+            parser::node::global::All::ConstantDeclaration {
+                identifier, value, ..
+            } => {
+                code.push_str(&format!(
+                    "const {} = __zyrahn_constant(() => {});",
+                    get_var_name(namespace, identifier),
+                    compile_value(value, source_map)
+                ));
+            }
+            // Structs are plain objects in JavaScript and don't need to be declared
+            parser::node::global::All::StructDeclaration { .. } => {}
+            parser::node::global::All::Namespace {
+                identifier, body, ..
+            } => {
+                let mut namespace = namespace.to_vec();
+                namespace.push(identifier.clone());
+
+                code.push_str(&compile(body, &namespace, source_map));
+            }
+            parser::node::global::All::Error {} => {
+                unreachable!("Code with syntax errors is never compiled")
+            }
+        }
+    }
+
+    code
+}
@@ -2,35 +2,367 @@ use crate::{parser::node::Node, *};
 
 mod block;
 mod expression;
+mod r#extern;
+mod global;
 mod std;
 
-fn get_var_name(namespace: &Vec<String>, identifier: &String) -> String {
-    format!("v{}__0{}", namespace.join("0"), identifier)
+/// Helper functions the generated code depends on
+const RUNTIME: &str = include_str!("runtime.js");
+
+// Names are mangled so that every zyrahn name maps to exactly one JavaScript name. Each segment of
+// the path is prefixed with its length in characters, e.g. `std::math::PI` becomes `v3std4math2PI`.
+// Since every mangled name contains a digit it can't be a reserved word in JavaScript, and since
+// none start with `__` they can't collide with the runtime either. Struct fields are mangled too,
+// so that fields like `__proto__` are ordinary properties.
+
+/// Mangles a namespace path together with the name at the end of it
+fn mangle_path(namespace: &[String], identifier: &str) -> String {
+    namespace
+        .iter()
+        .map(String::as_str)
+        .chain([identifier])
+        .map(|segment| format!("{}{}", segment.chars().count(), segment))
+        .collect()
 }
 
-fn get_func_name(
-    namespace: &Vec<String>,
-    identifier: &String,
-    args: &Vec<(bool, common::Type)>,
-) -> String {
+fn get_var_name(namespace: &[String], identifier: &str) -> String {
+    format!("v{}", mangle_path(namespace, identifier))
+}
+
+fn get_field_name(identifier: &str) -> String {
+    format!("m{}", mangle_path(&[], identifier))
+}
+
+fn get_type_name(ty: &common::Type) -> String {
+    match ty {
+        common::Type::Integer => "i".to_string(),
+        common::Type::Float => "f".to_string(),
+        common::Type::String => "s".to_string(),
+        common::Type::Boolean => "b".to_string(),
+        common::Type::Array(ty) => format!("a{}", get_type_name(ty)),
+        common::Type::Struct {
+            namespace,
+            identifier,
+        } => format!("S{}E", mangle_path(namespace, identifier)),
+        ty => unreachable!("'{}' can't be a type of an argument", ty),
+    }
+}
+
+fn get_func_name(namespace: &[String], identifier: &str, args: &[(bool, common::Type)]) -> String {
     let arg_types = args
-        .clone()
-        .into_iter()
-        .map(|(is_out, ty)| format!("{}{}", if is_out { "out_" } else { "" }, ty))
-        .collect::<Vec<_>>();
+        .iter()
+        .map(|(is_out, ty)| format!("{}{}", if *is_out { "o" } else { "" }, get_type_name(ty)))
+        .collect::<String>();
+
+    format!("f{}_{}", mangle_path(namespace, identifier), arg_types)
+}
+
+/// Turns a mangled variable or function name back into the name used in the zyrahn code, e.g.
+/// `f3std5print_s` becomes `std::print(str)`. Used to make stack traces readable, returns `None`
+/// if the name isn't a mangled name.
+pub fn demangle(name: &str) -> Option<String> {
+    let mut chars = name.chars().peekable();
+
+    fn path(chars: &mut ::std::iter::Peekable<::std::str::Chars>) -> Option<String> {
+        let mut segments = vec![];
+
+        while chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+            let mut len = 0usize;
+            while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                len = len.checked_mul(10)?.checked_add(digit as usize)?;
+                chars.next();
+            }
+
+            let segment = chars.by_ref().take(len).collect::<String>();
+            if segment.chars().count() != len {
+                return None;
+            }
+            segments.push(segment);
+        }
+
+        if segments.is_empty() {
+            return None;
+        }
+
+        Some(segments.join("::"))
+    }
+
+    fn ty(chars: &mut ::std::iter::Peekable<::std::str::Chars>) -> Option<String> {
+        match chars.next()? {
+            'i' => Some("int".to_string()),
+            'f' => Some("flt".to_string()),
+            's' => Some("str".to_string()),
+            'b' => Some("bln".to_string()),
+            'a' => Some(format!("[{}]", ty(chars)?)),
+            'S' => {
+                let name = path(chars)?;
+                (chars.next()? == 'E').then_some(name)
+            }
+            _ => None,
+        }
+    }
+
+    match chars.next()? {
+        'v' => {
+            let name = path(&mut chars)?;
+            chars.peek().is_none().then_some(name)
+        }
+        'f' => {
+            let name = path(&mut chars)?;
+            if chars.next()? != '_' {
+                return None;
+            }
+
+            let mut args = vec![];
+            while chars.peek().is_some() {
+                if chars.next_if_eq(&'o').is_some() {
+                    args.push(format!("out {}", ty(&mut chars)?));
+                } else {
+                    args.push(ty(&mut chars)?);
+                }
+            }
+
+            Some(format!("{}({})", name, args.join(", ")))
+        }
+        _ => None,
+    }
+}
+
+/// Turns a string into a JavaScript string literal, including the quotes
+fn quote_string(value: &str) -> String {
+    let mut quoted = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            // Other control characters, the line and paragraph separators which older engines
+            // don't allow in strings, and `<` so that the code can be put in a `<script>` tag
+            // without `</script>` or `<!--` in a string ending it, are written as code points
+            c if c.is_control() || c == '\u{2028}' || c == '\u{2029}' || c == '<' => {
+                quoted.push_str(&format!("\\u{{{:x}}}", c as u32));
+            }
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// Returns the location of a node in the zyrahn code as a JavaScript string literal, used in
+/// runtime errors
+fn get_location<C: span::Spanned>(node: &C, source_map: &span::SourceMap) -> String {
+    let span = node.span();
+    let (start, _) = source_map.resolve(span);
 
-    format!(
-        "f{}__0{}__0{}",
-        namespace.join("0"),
-        arg_types.join("0"),
-        identifier
-    )
+    quote_string(&format!(
+        "{} ln: {} cl: {}",
+        source_map.file(span.file_id).name(),
+        start.line,
+        start.column
+    ))
 }
 
+/// Compiles an expression whose value is about to be stored, e.g. in a variable or an argument.
+/// Structs and arrays are objects in JavaScript so they are copied, otherwise two variables would
+/// share the same value. A struct or array init already creates a new object and doesn't have to be
+/// copied, and neither do the other types since they're immutable.
+fn compile_value(
+    value: &Node<parser::node::expression::AllWithType>,
+    source_map: &span::SourceMap,
+) -> String {
+    let is_init = matches!(
+        value.node,
+        parser::node::expression::AllWithType::SingleDataUnit {
+            value: parser::node::expression::SingleDataUnit::StructInit { .. }
+                | parser::node::expression::SingleDataUnit::ArrayInit { .. },
+            ..
+        }
+    );
+
+    match value.node.ty() {
+        common::Type::Struct { .. } | common::Type::Array(_) if !is_init => {
+            format!("__zyrahn_copy({})", expression::compile(value, source_map))
+        }
+        _ => expression::compile(value, source_map),
+    }
+}
+
+/// Compiles the tree to JavaScript, the source map has the files of the tree and is used for the
+/// locations in runtime errors
 pub fn compile(
-    ast: &Vec<Node<parser::node::block::All<Node<parser::node::expression::AllWithType>>>>,
+    ast: &parser::node::Global<parser::node::expression::AllWithType>,
+    source_map: &span::SourceMap,
 ) -> String {
-    let code = block::compile(ast);
+    let mut code = RUNTIME.to_string();
+    code.push_str(&global::compile(ast, &[], source_map));
+    code.push_str("__zyrahn_init_constants();");
+
+    let has_main = ast.iter().any(|node| match &node.node {
+        parser::node::global::All::FunctionDeclaration {
+            identifier,
+            arguments,
+            ..
+        } => identifier == "main" && arguments.is_empty(),
+        _ => false,
+    });
+
+    if has_main {
+        code.push_str(&format!("{}();", get_func_name(&[], "main", &[])));
+    }
+
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile_freestanding(code: &str) -> String {
+        let mut source_map = span::SourceMap::new();
+        let file_id = source_map.add_file("main.zy", code);
+        let (tokens, _) = lexer::tokenize(code, file_id);
+        let (ast, _) = parser::gen(&tokens);
+        let typed_ast = static_analyzer::evaluate_freestanding(&ast).unwrap();
+
+        compile(&typed_ast, &source_map)
+    }
+
+    #[test]
+    fn mangling() {
+        let ns = |n: &[&str]| n.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let point = common::Type::Struct {
+            namespace: ns(&["geo"]),
+            identifier: "Point".to_string(),
+        };
+
+        assert_eq!(get_var_name(&ns(&["std", "math"]), "PI"), "v3std4math2PI");
+        assert_eq!(get_var_name(&[], "größe"), "v5größe");
+        assert_eq!(get_field_name("__proto__"), "m9__proto__");
+        assert_eq!(
+            get_func_name(&ns(&["std"]), "print", &[(false, common::Type::String)]),
+            "f3std5print_s"
+        );
+
+        // Names that were equal with digits as separators are now different
+        assert_ne!(
+            get_var_name(&ns(&["a0b"]), "c"),
+            get_var_name(&ns(&["a", "b"]), "c")
+        );
+        assert_ne!(get_var_name(&[], "a1"), get_var_name(&ns(&["a"]), ""));
+        assert_ne!(
+            get_func_name(
+                &[],
+                "f",
+                &[(false, common::Type::Array(Box::new(common::Type::Integer)))]
+            ),
+            get_func_name(&[], "f", &[(false, common::Type::Integer)]),
+        );
+
+        assert_eq!(demangle("v3std4math2PI").as_deref(), Some("std::math::PI"));
+        assert_eq!(demangle("v5größe").as_deref(), Some("größe"));
+        assert_eq!(
+            demangle(&get_func_name(
+                &ns(&["geo"]),
+                "move2",
+                &[
+                    (true, point.clone()),
+                    (false, common::Type::Array(Box::new(point))),
+                    (true, common::Type::Integer),
+                ]
+            ))
+            .as_deref(),
+            Some("geo::move2(out geo::Point, [geo::Point], out int)")
+        );
+        assert_eq!(demangle("f4main_").as_deref(), Some("main()"));
+
+        assert_eq!(demangle("__zyrahn_copy"), None);
+        assert_eq!(demangle("v3ab"), None);
+        assert_eq!(demangle("f1a_x"), None);
+        assert_eq!(demangle("v"), None);
+    }
+
+    #[test]
+    fn compound_assignment() {
+        let code = compile_freestanding(
+            "fnc i() -> int { ret 0; } fnc main() { var a = [1]; a[i()] += 5; var b = 1; b *= 2; }",
+        );
+        let index = get_func_name(&[], "i", &[]);
+
+        // Once in the declaration of the function and once in the assignment
+        assert_eq!(code.matches(&format!("{}()", index)).count(), 2);
+        assert!(code.contains(&format!(
+            "__zyrahn_update({}.value, {}(), ",
+            get_var_name(&[], "a"),
+            index
+        )));
+        assert!(code.contains(&format!("{}.value *= ", get_var_name(&[], "b"))));
+    }
+
+    #[test]
+    fn struct_fields() {
+        let code = compile_freestanding(
+            "struct A { __proto__: int } fnc main() { var a = A { __proto__: 1 }; a.__proto__ = 2; }",
+        );
+
+        assert!(!code.contains(".__proto__"));
+        assert!(code.contains("{m9__proto__: "));
+        assert!(code.contains(".value.m9__proto__ = "));
+    }
+
+    #[test]
+    fn constant_order() {
+        let code = compile_freestanding("cst A = B; cst B = 1; fnc main() {}");
+
+        // `A` is initialized with `B` when it's first used, or before `main` is called
+        assert!(code.contains("const v1A = __zyrahn_constant(() => v1B.value);"));
+        assert!(code.ends_with("__zyrahn_init_constants();f4main_();"));
+    }
+
+    #[test]
+    fn struct_copy() {
+        let code = compile_freestanding(
+            "struct P { x: int } fnc main() { var a = P { x: 1 }; var b = a; b.x = 2; }",
+        );
+        assert!(code.contains("let v1b = { value: __zyrahn_copy(v1a.value) };"));
+
+        // Integers are BigInts, which are copied as they are
+        let copy = &RUNTIME[RUNTIME.find("function __zyrahn_copy").unwrap()..];
+        let copy = &copy[..copy.find("\n}\n").unwrap()];
+        assert!(copy.contains("return value;"));
+        assert!(!copy.contains("JSON"));
+        assert!(!copy.contains("structuredClone"));
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote_string(""), r#""""#);
+        assert_eq!(quote_string(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(quote_string(r"C:\dir\"), r#""C:\\dir\\""#);
+        assert_eq!(quote_string("a\nb\r\n\tc"), r#""a\nb\r\n\tc""#);
+        assert_eq!(quote_string("\0\u{1b}"), r#""\u{0}\u{1b}""#);
+        assert_eq!(
+            quote_string("a\u{2028}b\u{2029}"),
+            r#""a\u{2028}b\u{2029}""#
+        );
+        assert_eq!(
+            quote_string("</script><!--"),
+            r#""\u{3c}/script>\u{3c}!--""#
+        );
+        assert_eq!(quote_string("größe 🦀"), "\"größe 🦀\"");
+    }
+
+    #[test]
+    fn integer_literals() {
+        let code = compile_freestanding(
+            "fnc main() { var a = 9007199254740993; var b = 0x7FFFFFFFFFFFFFFF; }",
+        );
 
-    return code;
+        assert!(code.contains("let v1a = { value: 9007199254740993n };"));
+        assert!(code.contains("let v1b = { value: 9223372036854775807n };"));
+    }
 }
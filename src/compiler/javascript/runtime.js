@@ -0,0 +1,67 @@
+// Helpers used by the generated code, included at the top of every compiled program
+
+function __zyrahn_check_index(array, index, location) {
+  if (index < 0n || index >= BigInt(array.length)) {
+    throw new Error(
+      `Index out of bounds at ${location}: the length is ${array.length} but the index is ${index}`,
+    );
+  }
+
+  return Number(index);
+}
+
+function __zyrahn_get(array, index, location) {
+  return array[__zyrahn_check_index(array, index, location)];
+}
+
+function __zyrahn_set(array, index, value, location) {
+  array[__zyrahn_check_index(array, index, location)] = value;
+}
+
+// Used for compound assignments to elements, `update` gets the current element and returns the new
+function __zyrahn_update(array, index, update, location) {
+  const i = __zyrahn_check_index(array, index, location);
+  array[i] = update(array[i]);
+}
+
+// Deep copies a value, used when a struct or an array is stored or passed to a function. Values are
+// only ever arrays, plain objects and primitives, so copying them recursively is enough. It isn't
+// left to `structuredClone` since that's missing in older runtimes, e.g. Node before 17, and it
+// can't go through `JSON` since that throws on BigInt.
+function __zyrahn_copy(value) {
+  if (Array.isArray(value)) {
+    return value.map(__zyrahn_copy);
+  }
+
+  if (typeof value === "object") {
+    return Object.fromEntries(
+      Object.entries(value).map(([field, value]) => [field, __zyrahn_copy(value)]),
+    );
+  }
+
+  // Integers, floats, strings and booleans are immutable and don't have to be copied
+  return value;
+}
+
+// Constants are initialized the first time they're used, so that they can use constants that are
+// declared after them. `__zyrahn_init_constants` initializes the rest in the order they're declared.
+const __zyrahn_constants = [];
+
+function __zyrahn_constant(init) {
+  const constant = {
+    get value() {
+      const value = init();
+      Object.defineProperty(constant, "value", { value });
+      return value;
+    },
+  };
+
+  __zyrahn_constants.push(constant);
+  return constant;
+}
+
+function __zyrahn_init_constants() {
+  for (const constant of __zyrahn_constants) {
+    constant.value;
+  }
+}
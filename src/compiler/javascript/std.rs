@@ -1,23 +1,34 @@
-use super::get_var_name;
+use crate::*;
 
-pub fn get_pre_defined_compiler(value: String) -> String {
-    let inp = get_var_name(&vec![], "input");
-    let out = get_var_name(&vec![], "output");
+/// Compiles a call to a function from `static_analyzer::built_in`, the arguments are already
+/// compiled
+pub fn compile_built_in(namespace: &[String], identifier: &str, arguments: &[String]) -> String {
+    match static_analyzer::Scope::combine_ns_name(namespace, identifier).as_str() {
+        "std::len" => format!("BigInt(({}).length)", arguments[0]),
+        name => unreachable!("'{}' is not a built-in function", name),
+    }
+}
 
-    match value.as_str() {
-        "print-str" => format!("console.log({}.value)", inp),
-        "print-int" => format!("console.log({}.value)", inp),
-        "print-flt" => format!("console.log({}.value)", inp),
-        "print-bln" => format!("console.log({}.value)", inp),
-        "parse_str-int" => {
-            format!("let a = parseInt({}.value);if (!isFinite(a)) {{ return false }} {}.value = BigInt(a); return true;", inp, out)
-        }
-        "parse_str-flt" => {
-            format!("let a = parseFloat({}.value);if (!isFinite(a)) {{ return false }} {}.value = a; return true;", inp, out)
+/// Compiles the body of an intrinsic function, the arguments are the names of the argument
+/// variables
+pub fn compile_intrinsic(intrinsic: intrinsic::Intrinsic, arguments: &[String]) -> String {
+    use intrinsic::Intrinsic;
+
+    match intrinsic {
+        Intrinsic::PrintStr | Intrinsic::PrintFlt | Intrinsic::PrintBln => {
+            format!("console.log({}.value);", arguments[0])
         }
-        "to_int" => format!("return BigInt(Math.round({}.value))", inp),
-        "to_flt" => format!("return Number({}.value)", inp),
-        "math::sqrt" => format!("return Math.sqrt({}.value)", inp),
-        _ => unimplemented!(),
+        Intrinsic::PrintInt => format!("console.log(String({}.value));", arguments[0]),
+        Intrinsic::ParseStrInt => format!(
+            "let a = parseInt({}.value);if (!isFinite(a)) {{ return false }} {}.value = BigInt(a); return true;",
+            arguments[0], arguments[1]
+        ),
+        Intrinsic::ParseStrFlt => format!(
+            "let a = parseFloat({}.value);if (!isFinite(a)) {{ return false }} {}.value = a; return true;",
+            arguments[0], arguments[1]
+        ),
+        Intrinsic::ToInt => format!("return BigInt(Math.round({}.value));", arguments[0]),
+        Intrinsic::ToFlt => format!("return Number({}.value);", arguments[0]),
+        Intrinsic::MathSqrt => format!("return Math.sqrt({}.value);", arguments[0]),
     }
 }
@@ -4,17 +4,40 @@ use crate::*;
 pub enum StaticAnalyzerErrorType {
     TypeMismatchOp(lexer::TokenType, common::Type, common::Type),
     TypeMismatchAssign(common::Type, common::Type),
+    TypeMismatchCondition(common::Type),
+    TypeMismatchReturn(common::Type, common::Type),
+    TypeMismatchArrayElement(common::Type, common::Type),
+    TypeMismatchIndex(common::Type),
+    CannotInferEmptyArray,
+    InvalidVariableType(common::Type),
     OperationNotSupportedNeg(common::Type),
     OperationNotSupportedNot(common::Type),
     OperationNotSupported(lexer::TokenType, common::Type),
     VariableNotDefined(String, Vec<String>),
     FunctionNotDefined(String, Vec<String>),
     FunctionArgumentMismatch(String, Vec<String>, Vec<(bool, common::Type)>),
+    FunctionAlreadyDeclared(String, Vec<String>, Vec<(bool, common::Type)>),
+    StructAlreadyDeclared(String, Vec<String>),
+    ConstantCycle(String, Vec<String>),
+    TypeNotDefined(common::Type),
+    StructFieldMissing(common::Type, String),
+    StructFieldNotDefined(common::Type, String),
     CannotCallNonFunction,
     FeatureNotImplemented(String),
-    CompilerCustomCodePreDefined,
+    IntrinsicOutsideOfPrelude,
+    IntrinsicNotDefined(String),
+    IntrinsicSignatureMismatch(String),
+    InvalidExternType(common::Type),
     VariableAlreadyDeclared(String),
     CannotUseNonIdentifierAsOutArgument,
+    CannotUseConstantAsOutArgument(String),
+    CannotAssignToConstant(String),
+    OutsideOfLoop(lexer::TokenType),
+    ReturnValueInEmptyFunction,
+    MissingReturnValue(common::Type),
+    MissingReturn(String, Vec<String>),
+    /// A statement or declaration that failed to parse, the parser has already reported why
+    SyntaxError,
 }
 
 impl std::fmt::Display for StaticAnalyzerErrorType {
@@ -25,15 +48,105 @@ impl std::fmt::Display for StaticAnalyzerErrorType {
             ET::VariableAlreadyDeclared(name) => {
                 write!(f, "Variable '{}' is already declared", name)
             }
+            ET::TypeMismatchReturn(expected, found) => {
+                write!(
+                    f,
+                    "Type mismatch for return: expected {}, found {}",
+                    expected, found
+                )
+            }
+            ET::TypeMismatchArrayElement(expected, found) => {
+                write!(
+                    f,
+                    "Type mismatch for array element: expected {}, found {}",
+                    expected, found
+                )
+            }
+            ET::TypeMismatchIndex(ty) => {
+                write!(
+                    f,
+                    "Type mismatch for index: expected {}, found {}",
+                    common::Type::Integer,
+                    ty
+                )
+            }
+            ET::InvalidVariableType(ty) => {
+                write!(f, "Cannot declare a variable of type '{}'", ty)
+            }
+            ET::CannotInferEmptyArray => {
+                write!(f, "Cannot infer the type of an empty array")
+            }
+            ET::SyntaxError => write!(f, "Syntax error"),
+            ET::ReturnValueInEmptyFunction => {
+                write!(
+                    f,
+                    "Cannot return a value from a function without a return type"
+                )
+            }
+            ET::MissingReturnValue(ty) => {
+                write!(f, "Missing return value, expected {}", ty)
+            }
+            ET::MissingReturn(name, ns) => {
+                let function_name = {
+                    if !ns.is_empty() {
+                        format!("{}::{}", ns.join("::"), name)
+                    } else {
+                        name.clone()
+                    }
+                };
+
+                write!(
+                    f,
+                    "Function '{}' does not return a value on every path",
+                    function_name
+                )
+            }
+            ET::TypeNotDefined(ty) => {
+                write!(f, "Type '{}' is not declared", ty)
+            }
+            ET::StructFieldMissing(ty, field) => {
+                write!(f, "Missing field '{}' in initializer of '{}'", field, ty)
+            }
+            ET::StructFieldNotDefined(ty, field) => {
+                write!(f, "Struct '{}' has no field '{}'", ty, field)
+            }
+            ET::OutsideOfLoop(token_type) => {
+                write!(f, "'{}' can only be used inside of a loop", token_type)
+            }
+            ET::CannotUseConstantAsOutArgument(name) => {
+                write!(f, "Cannot use constant '{}' as out argument", name)
+            }
+            ET::CannotAssignToConstant(name) => {
+                write!(f, "Cannot assign to constant '{}'", name)
+            }
             ET::CannotUseNonIdentifierAsOutArgument => {
                 write!(f, "Cannot use non-identifier as out argument")
             }
-            ET::CompilerCustomCodePreDefined => {
-                write!(f, "Compiler set custom code pre-defined error")
+            ET::IntrinsicOutsideOfPrelude => {
+                write!(f, "Intrinsics can only be declared in the standard library")
             }
+            ET::InvalidExternType(ty) => write!(
+                f,
+                "Type '{}' can't be passed to or returned from an extern function",
+                ty
+            ),
+            ET::IntrinsicNotDefined(id) => write!(f, "Intrinsic '{}' is not defined", id),
+            ET::IntrinsicSignatureMismatch(id) => write!(
+                f,
+                "The declaration of intrinsic '{}' doesn't match its signature",
+                id
+            ),
             ET::TypeMismatchAssign(left, right) => {
                 write!(f, "Type mismatch for assignment: {} != {}", left, right)
             }
+            ET::TypeMismatchCondition(ty) => {
+                write!(
+                    f,
+                    "Type mismatch for condition: expected {}, found {}",
+                    common::Type::Boolean,
+                    ty
+                )
+            }
             ET::TypeMismatchOp(token_type, left, right) => {
                 write!(
                     f,
@@ -52,7 +165,7 @@ impl std::fmt::Display for StaticAnalyzerErrorType {
             }
             ET::VariableNotDefined(name, ns) => {
                 let variable_name = {
-                    if ns.len() != 0 {
+                    if !ns.is_empty() {
                         format!("{}::{}", ns.join("::"), name)
                     } else {
                         name.clone()
@@ -63,7 +176,7 @@ impl std::fmt::Display for StaticAnalyzerErrorType {
             }
             ET::FunctionNotDefined(name, ns) => {
                 let function_name = {
-                    if ns.len() != 0 {
+                    if !ns.is_empty() {
                         format!("{}::{}", ns.join("::"), name)
                     } else {
                         name.clone()
@@ -74,7 +187,7 @@ impl std::fmt::Display for StaticAnalyzerErrorType {
             }
             ET::FunctionArgumentMismatch(name, ns, args) => {
                 let function_name = {
-                    if ns.len() != 0 {
+                    if !ns.is_empty() {
                         format!("{}::{}", ns.join("::"), name)
                     } else {
                         name.clone()
@@ -98,6 +211,58 @@ impl std::fmt::Display for StaticAnalyzerErrorType {
                         .join(", ")
                 )
             }
+            ET::FunctionAlreadyDeclared(name, ns, args) => {
+                let function_name = {
+                    if !ns.is_empty() {
+                        format!("{}::{}", ns.join("::"), name)
+                    } else {
+                        name.clone()
+                    }
+                };
+
+                write!(
+                    f,
+                    "Function '{}' is already declared with the overload: {}({})",
+                    function_name,
+                    function_name,
+                    args.iter()
+                        .map(|(is_out, arg)| {
+                            if *is_out {
+                                format!("out {}", arg)
+                            } else {
+                                format!("{}", arg)
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+            ET::StructAlreadyDeclared(name, ns) => {
+                let struct_name = {
+                    if !ns.is_empty() {
+                        format!("{}::{}", ns.join("::"), name)
+                    } else {
+                        name.clone()
+                    }
+                };
+
+                write!(f, "Struct '{}' is already declared", struct_name)
+            }
+            ET::ConstantCycle(name, ns) => {
+                let constant_name = {
+                    if !ns.is_empty() {
+                        format!("{}::{}", ns.join("::"), name)
+                    } else {
+                        name.clone()
+                    }
+                };
+
+                write!(
+                    f,
+                    "The value of constant '{}' depends on itself",
+                    constant_name
+                )
+            }
             ET::CannotCallNonFunction => write!(f, "Cannot call non-function"),
             ET::FeatureNotImplemented(feature) => {
                 write!(
@@ -114,7 +279,10 @@ impl std::fmt::Display for StaticAnalyzerErrorType {
 pub enum LexerErrorType {
     UnexpectedSymbol(String),
     InvalidNumber(String),
+    NumberOverflow(String),
     NonTerminatedString,
+    NonTerminatedComment,
+    InvalidEscape(String),
 }
 
 impl std::fmt::Display for LexerErrorType {
@@ -124,7 +292,10 @@ impl std::fmt::Display for LexerErrorType {
         match self {
             ET::UnexpectedSymbol(symbol) => write!(f, "Unexpected symbol '{}'", symbol),
             ET::InvalidNumber(number) => write!(f, "Invalid number '{}'", number),
+            ET::NumberOverflow(number) => write!(f, "Number '{}' is too large", number),
             ET::NonTerminatedString => write!(f, "String not terminated"),
+            ET::NonTerminatedComment => write!(f, "Block comment not terminated"),
+            ET::InvalidEscape(sequence) => write!(f, "Invalid escape sequence '{}'", sequence),
         }
     }
 }
@@ -136,8 +307,6 @@ pub enum ParserErrorType {
     UnexpectedCloseCurly,
     UnexpectedCloseSquare,
     SquareNotClosed,
-    // This will be used with struct inits later
-    #[allow(dead_code)]
     CurlyNotClosed,
     ParenNotClosed,
     UnexpectedExpression,
@@ -148,13 +317,20 @@ pub enum ParserErrorType {
     // e.g. `value.`
     NoPropertyOnAccess,
     FeatureNotImplemented(String),
-
-    CompilerCustomCodePreDefined,
+    // When blocks, namespaces or parentheses are nested deeper than `parser::MAX_NESTING_DEPTH`
+    NestingTooDeep,
 
     /// ---- Block ----
     MissingSemicolon,
     MissingIdentifier,
     StatementEndEarly,
+    InvalidAssignmentTarget,
+
+    /// ---- Global ----
+    DuplicateField(String),
+    InvalidExternSource(String),
+    InvalidExternExport(String),
+    MisplacedDocComment,
 
     /// ------ Generic error -------
     UnexpectedToken(lexer::TokenType),
@@ -173,9 +349,6 @@ impl std::fmt::Display for ParserErrorType {
             ET::CurlyNotClosed => write!(f, "Curly bracket not closed"),
             ET::ParenNotClosed => write!(f, "Parenthesis not closed"),
             ET::UnexpectedToken(token_type) => write!(f, "Unexpected token '{}'", token_type),
-            ET::CompilerCustomCodePreDefined => {
-                write!(f, "Compiler set custom code pre-defined error")
-            }
             ET::UnexpectedTokenExpected(token_type, expected) => write!(
                 f,
                 "Unexpected token '{}', expected '{}'",
@@ -195,9 +368,30 @@ impl std::fmt::Display for ParserErrorType {
             ET::FeatureNotImplemented(feature) => {
                 write!(f, "Feature '{}' not implemented yet for ast :(", feature)
             }
+            ET::NestingTooDeep => write!(
+                f,
+                "Nesting is deeper than the maximum of {} levels",
+                parser::MAX_NESTING_DEPTH
+            ),
             ET::MissingSemicolon => write!(f, "Missing semicolon"),
             ET::MissingIdentifier => write!(f, "Missing identifier"),
             ET::StatementEndEarly => write!(f, "Statement ended early"),
+            ET::InvalidAssignmentTarget => write!(f, "Invalid left-hand side of assignment"),
+            ET::DuplicateField(field) => write!(f, "Field '{}' is specified more than once", field),
+            ET::MisplacedDocComment => write!(
+                f,
+                "Doc comments can only be written before functions, structs, constants and namespaces"
+            ),
+            ET::InvalidExternSource(source) => write!(
+                f,
+                "Extern source '{}' should be written as \"module#export\"",
+                source
+            ),
+            ET::InvalidExternExport(export) => write!(
+                f,
+                "Extern export '{}' is not a valid JavaScript identifier",
+                export
+            ),
         }
     }
 }
@@ -208,12 +402,7 @@ where
     T: Sized + std::fmt::Display + std::fmt::Debug + Clone,
 {
     error_type: T,
-    cl_start: usize,
-    #[allow(dead_code)]
-    cl_end: usize,
-    ln_start: usize,
-    #[allow(dead_code)]
-    ln_end: usize,
+    span: span::Span,
 }
 
 impl<T> std::error::Error for Error<T> where T: Sized + Clone + std::fmt::Display + std::fmt::Debug {}
@@ -222,78 +411,40 @@ impl<T> Error<T>
 where
     T: Sized + std::fmt::Display + std::fmt::Debug + Clone,
 {
-    pub fn new(
-        error_type: T,
-        ln_start: usize,
-        cl_start: usize,
-        ln_end: usize,
-        cl_end: usize,
-    ) -> Error<T> {
-        Error {
-            error_type,
-            cl_start,
-            cl_end,
-            ln_start,
-            ln_end,
-        }
+    pub fn new(error_type: T, span: span::Span) -> Error<T> {
+        Error { error_type, span }
     }
 
-    pub fn from_cl_ln<V: Sized>(error_type: T, v: &V) -> Error<T>
-    where
-        V: cl_ln::ClLn,
-    {
-        Error::new(
-            error_type,
-            v.ln_start(),
-            v.cl_start(),
-            v.ln_end(),
-            v.cl_end(),
-        )
+    pub fn from_span<V: span::Spanned>(error_type: T, v: &V) -> Error<T> {
+        Error::new(error_type, v.span())
     }
-}
 
-impl<T> cl_ln::ClLn for Error<T>
-where
-    T: Sized + std::fmt::Display + std::fmt::Debug + Clone,
-{
-    fn cl_start(&self) -> usize {
-        self.cl_start
+    pub fn error_type(&self) -> &T {
+        &self.error_type
     }
 
-    fn cl_end(&self) -> usize {
-        self.cl_end
-    }
-
-    fn ln_start(&self) -> usize {
-        self.ln_start
+    /// Formats the error together with the line and column it's at, e.g.
+    /// `main.zy ln: 1 cl: 9-10 - Statement ended early`
+    pub fn report(&self, source_map: &span::SourceMap) -> String {
+        format!("{} - {}", source_map.location(self.span), self.error_type)
     }
+}
 
-    fn ln_end(&self) -> usize {
-        self.ln_end
+impl<T> span::Spanned for Error<T>
+where
+    T: Sized + std::fmt::Display + std::fmt::Debug + Clone,
+{
+    fn span(&self) -> span::Span {
+        self.span
     }
 }
 
+/// Only the error itself, since the position needs a `span::SourceMap`, see `Error::report`
 impl<T> std::fmt::Display for Error<T>
 where
     T: Sized + std::fmt::Display + std::fmt::Debug + Clone,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let cl = {
-            if self.cl_start == self.cl_end {
-                format!("{}", self.cl_start)
-            } else {
-                format!("{}-{}", self.cl_start, self.cl_end)
-            }
-        };
-
-        let ln = {
-            if self.ln_start == self.ln_end {
-                format!("{}", self.ln_start)
-            } else {
-                format!("{}-{}", self.ln_start, self.ln_end)
-            }
-        };
-
-        write!(f, "ln: {} cl: {} - {}", ln, cl, self.error_type)
+        write!(f, "{}", self.error_type)
     }
 }
@@ -0,0 +1,80 @@
+//! Functions of the standard library that are implemented by the compiler. They are declared in
+//! `std.zy` with `intrinsic fnc`, the static analyzer checks the declaration against the signature
+//! here and every backend lowers each intrinsic to its own code.
+//!
+//! # Examples
+//! ```text
+//! intrinsic fnc sqrt(input: flt) -> flt = "math::sqrt";
+//! ```
+use crate::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Intrinsic {
+    PrintStr,
+    PrintInt,
+    PrintFlt,
+    PrintBln,
+    ParseStrInt,
+    ParseStrFlt,
+    ToInt,
+    ToFlt,
+    MathSqrt,
+}
+
+impl Intrinsic {
+    pub const ALL: [Intrinsic; 9] = [
+        Intrinsic::PrintStr,
+        Intrinsic::PrintInt,
+        Intrinsic::PrintFlt,
+        Intrinsic::PrintBln,
+        Intrinsic::ParseStrInt,
+        Intrinsic::ParseStrFlt,
+        Intrinsic::ToInt,
+        Intrinsic::ToFlt,
+        Intrinsic::MathSqrt,
+    ];
+
+    /// The id the intrinsic is referred to by in `std.zy`
+    pub fn id(self) -> &'static str {
+        match self {
+            Intrinsic::PrintStr => "print-str",
+            Intrinsic::PrintInt => "print-int",
+            Intrinsic::PrintFlt => "print-flt",
+            Intrinsic::PrintBln => "print-bln",
+            Intrinsic::ParseStrInt => "parse_str-int",
+            Intrinsic::ParseStrFlt => "parse_str-flt",
+            Intrinsic::ToInt => "to_int",
+            Intrinsic::ToFlt => "to_flt",
+            Intrinsic::MathSqrt => "math::sqrt",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Intrinsic> {
+        Intrinsic::ALL
+            .into_iter()
+            .find(|intrinsic| intrinsic.id() == id)
+    }
+
+    /// The argument types, with if they're out arguments, together with the return type
+    pub fn signature(self) -> (Vec<(bool, common::Type)>, common::Type) {
+        use common::Type;
+
+        match self {
+            Intrinsic::PrintStr => (vec![(false, Type::String)], Type::Empty),
+            Intrinsic::PrintInt => (vec![(false, Type::Integer)], Type::Empty),
+            Intrinsic::PrintFlt => (vec![(false, Type::Float)], Type::Empty),
+            Intrinsic::PrintBln => (vec![(false, Type::Boolean)], Type::Empty),
+            Intrinsic::ParseStrInt => (
+                vec![(false, Type::String), (true, Type::Integer)],
+                Type::Boolean,
+            ),
+            Intrinsic::ParseStrFlt => (
+                vec![(false, Type::String), (true, Type::Float)],
+                Type::Boolean,
+            ),
+            Intrinsic::ToInt => (vec![(false, Type::Float)], Type::Integer),
+            Intrinsic::ToFlt => (vec![(false, Type::Integer)], Type::Float),
+            Intrinsic::MathSqrt => (vec![(false, Type::Float)], Type::Float),
+        }
+    }
+}
@@ -11,21 +11,33 @@ use crate::*;
 mod token;
 pub use token::*;
 
-const SYMBOLS: &[&'static str] = &[
+const SYMBOLS: &[&str] = &[
     "+", "-", "*", "/", "(", ")", "[", "]", "{", "}", "->", ";", ":", "::", ",", ".", "=", "+=",
-    "-=", "==", "!=", "<", ">", "<=", ">=", "||", "&&", "!", "%", "**",
+    "-=", "*=", "/=", "%=", "**=", "==", "!=", "<", ">", "<=", ">=", "||", "&&", "!", "%", "**",
 ];
 
 fn is_symbol_char(char: char) -> bool {
     SYMBOLS.iter().any(|s| s.chars().any(|c| c == char))
 }
 
+/// Returns if the character can start an identifier or a keyword, i.e. if it's an underscore or
+/// has the Unicode `XID_Start` property
+fn is_word_start(char: char) -> bool {
+    char == '_' || unicode_ident::is_xid_start(char)
+}
+
+/// Returns if the character can be part of an identifier or a keyword after the first character,
+/// which also allows digits
 fn is_word_char(char: char) -> bool {
-    matches!(char, 'a'..='z' | 'A'..='Z' | '_')
+    unicode_ident::is_xid_continue(char)
 }
 
 fn is_number_char(char: char) -> bool {
-    matches!(char, '0'..='9')
+    char.is_ascii_digit()
+}
+
+fn is_white_space(char: char) -> bool {
+    matches!(char, ' ' | '\t' | '\r' | '\n')
 }
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
@@ -36,206 +48,513 @@ enum Eval {
     String,
 }
 
-impl Eval {
-    fn from_char(char: char) -> Option<Eval> {
-        if is_symbol_char(char) {
-            Some(Eval::Symbol)
-        } else if is_word_char(char) {
-            Some(Eval::Word)
-        } else if is_number_char(char) {
-            Some(Eval::Number)
-        } else {
-            None
+/// A character of the code together with the file it's in and its byte offset
+type Char = (span::FileId, usize, char);
+
+/// Returns the span from the first character to the last character, both included
+fn span(first: &Char, last: &Char) -> span::Span {
+    span::Span::new(first.0, first.1, last.1 + last.2.len_utf8())
+}
+
+/// Splits the code into characters with their positions. A `\r` before a `\n` is dropped so that
+/// code with Windows line endings is lexed the same way as any other code, and so is the byte order
+/// mark some editors start the file with.
+fn chars(code: &str, file_id: span::FileId) -> Vec<Char> {
+    let mut chars = vec![];
+    let mut iter = code.char_indices().peekable();
+
+    while let Some((offset, c)) = iter.next() {
+        let is_crlf = c == '\r' && matches!(iter.peek(), Some((_, '\n')));
+        let is_bom = c == '\u{feff}' && offset == 0;
+
+        if !is_crlf && !is_bom {
+            chars.push((file_id, offset, c));
         }
     }
+
+    chars
+}
+
+/// Returns the index after the last character, starting at `start`, that matches the predicate
+fn take_while(chars: &[Char], start: usize, predicate: impl Fn(char) -> bool) -> usize {
+    start
+        + chars[start..]
+            .iter()
+            .take_while(|(_, _, c)| predicate(*c))
+            .count()
 }
 
-/// Turns a string of code into a list of tokens.
+/// Turns a string of code into a list of tokens. The spans of the tokens are in the file with the
+/// id `file_id`, see `span::SourceMap`.
 ///
 /// # Examples
 /// ```
 /// use zyrahn::lexer;
 ///
-/// let tokens = lexer::tokenize("3 + 3");
+/// let (tokens, errors) = lexer::tokenize("3 + 3", 0);
 ///
-/// if let Err(e) = tokens {
+/// for e in &errors {
 ///     println!("{}", e);
-///     return;
 /// }
 ///
-/// let tokens = tokens.unwrap();
-///
 /// println!("{:?}", tokens); // [IntegerLiteral(3), Add, IntegerLiteral(3)]
 /// ```
-pub fn tokenize(code: &str) -> Result<Vec<Token>, error::Error<error::LexerErrorType>> {
-    let mut tokens = vec![];
+pub fn tokenize(
+    code: &str,
+    file_id: span::FileId,
+) -> (Vec<Token>, Vec<error::Error<error::LexerErrorType>>) {
+    let chars = chars(code, file_id);
 
-    let lines = code.split('\n');
+    let mut tokens = vec![];
+    let mut errors = vec![];
+    let mut i = 0;
+
+    while let Some(&(_, _, c)) = chars.get(i) {
+        let next = chars.get(i + 1).map(|(_, _, c)| *c);
+
+        match c {
+            c if is_white_space(c) => i += 1,
+            '/' if next == Some('/') => {
+                let end = take_while(&chars, i, |c| c != '\n');
+                let comment = chars[i..end].iter().map(|(_, _, c)| c).collect::<String>();
+
+                // `///` starts a doc comment, but `////` is a normal comment
+                if let Some(doc) = comment
+                    .strip_prefix("///")
+                    .filter(|doc| !doc.starts_with('/'))
+                {
+                    tokens.push(Token {
+                        token_type: TokenType::DocComment(
+                            doc.strip_prefix(' ').unwrap_or(doc).to_string(),
+                        ),
+                        span: span(&chars[i], &chars[end - 1]),
+                    });
+                }
 
-    for (ln, line) in lines.enumerate() {
-        let mut cur_eval: Option<Eval> = None;
-        let mut cur_text = String::new();
-        let mut cl_start = 0;
-        let mut chars = line.chars().enumerate().peekable();
+                i = end;
+            }
+            '/' if next == Some('*') => match block_comment(&chars, i) {
+                Ok(end) => i = end + 1,
+                Err(e) => {
+                    // The comment runs to the end of the code
+                    errors.push(e);
+                    i = chars.len();
+                }
+            },
+            '"' => match string(&chars, i, &mut errors) {
+                Ok((token, end)) => {
+                    tokens.push(token);
+                    i = end + 1;
+                }
+                Err(e) => {
+                    // The string runs to the end of the code
+                    tokens.push(error_token(&e));
+                    errors.push(e);
+                    i = chars.len();
+                }
+            },
+            'r' if matches!(next, Some('"' | '#')) => match raw_string(&chars, i) {
+                Ok((token, end)) => {
+                    tokens.push(token);
+                    i = end + 1;
+                }
+                Err(e) => {
+                    let end = match e.error_type() {
+                        // Continue after the character that should have been a `"`
+                        error::LexerErrorType::UnexpectedSymbol(_) => {
+                            take_while(&chars, i + 1, |c| c == '#') + 1
+                        }
+                        _ => chars.len(),
+                    };
 
-        let mut is_esc = false;
+                    tokens.push(error_token(&e));
+                    errors.push(e);
+                    i = end;
+                }
+            },
+            c if is_word_start(c) => {
+                let end = take_while(&chars, i, is_word_char);
+                let word = chars[i..end].iter().map(|(_, _, c)| c).collect::<String>();
+
+                push_token(
+                    parse_token(&word, Eval::Word, span(&chars[i], &chars[end - 1])),
+                    &mut tokens,
+                    &mut errors,
+                );
+                i = end;
+            }
+            c if is_number_char(c) => {
+                let end = number_end(&chars, i);
+                let number = chars[i..end].iter().map(|(_, _, c)| c).collect::<String>();
+
+                push_token(
+                    parse_token(&number, Eval::Number, span(&chars[i], &chars[end - 1])),
+                    &mut tokens,
+                    &mut errors,
+                );
+                i = end;
+            }
+            c if is_symbol_char(c) => {
+                // Symbols are at most three characters long, so try to match the longest symbol
+                // first
+                let len = (1..=3)
+                    .rev()
+                    .find(|len| {
+                        let symbol = chars[i..]
+                            .iter()
+                            .take(*len)
+                            .map(|(_, _, c)| c)
+                            .collect::<String>();
+
+                        symbol.chars().count() == *len && SYMBOLS.contains(&symbol.as_str())
+                    })
+                    .unwrap_or(1);
+
+                let symbol = chars[i..i + len]
+                    .iter()
+                    .map(|(_, _, c)| c)
+                    .collect::<String>();
+
+                push_token(
+                    parse_token(&symbol, Eval::Symbol, span(&chars[i], &chars[i + len - 1])),
+                    &mut tokens,
+                    &mut errors,
+                );
+                i += len;
+            }
+            c => {
+                let e = error::Error::new(
+                    error::LexerErrorType::UnexpectedSymbol(c.to_string()),
+                    span(&chars[i], &chars[i]),
+                );
+
+                tokens.push(error_token(&e));
+                errors.push(e);
+                i += 1;
+            }
+        }
+    }
 
-        while let Some((cl, c)) = chars.next() {
-            let c = c.clone();
+    (tokens, errors)
+}
 
-            if c == '"' {
-                if is_esc {
-                    cur_text.push(c);
-                    is_esc = false;
-                    continue;
-                }
+/// Creates an `Error` token covering the code of a lexical error, so that the parser knows that
+/// something was there
+fn error_token(error: &error::Error<error::LexerErrorType>) -> Token {
+    use span::Spanned;
 
-                if cur_eval == Some(Eval::String) {
-                    tokens.push(parse_token(&cur_text, Eval::String, ln, cl_start, cl)?);
-                    cur_eval = None;
-                    cur_text.clear();
-                    continue;
-                }
+    Token {
+        token_type: TokenType::Error,
+        span: error.span(),
+    }
+}
 
-                cur_eval = Some(Eval::String);
-                cur_text.clear();
-                cl_start = cl;
+/// Adds the token, or an `Error` token in its place if it's invalid
+fn push_token(
+    token: Result<Token, error::Error<error::LexerErrorType>>,
+    tokens: &mut Vec<Token>,
+    errors: &mut Vec<error::Error<error::LexerErrorType>>,
+) {
+    match token {
+        Ok(token) => tokens.push(token),
+        Err(e) => {
+            tokens.push(error_token(&e));
+            errors.push(e);
+        }
+    }
+}
 
-                continue;
+/// Skips a block comment starting with the `/*` at index `start`. Block comments can be nested,
+/// so every `/*` has to be closed by its own `*/`. Returns the index of the `/` closing the
+/// comment.
+///
+/// # Example
+/// ```text
+/// /* outer /* inner */ still a comment */
+/// ```
+fn block_comment(
+    chars: &[Char],
+    start: usize,
+) -> Result<usize, error::Error<error::LexerErrorType>> {
+    let mut depth = 0;
+    let mut i = start;
+
+    while i + 1 < chars.len() {
+        match (chars[i].2, chars[i + 1].2) {
+            ('/', '*') => {
+                depth += 1;
+                i += 2;
             }
+            ('*', '/') => {
+                depth -= 1;
+                i += 2;
 
-            if cur_eval == Some(Eval::String) {
-                if c == '\\' {
-                    if is_esc {
-                        cur_text.push(c);
-                        is_esc = false;
-                    } else {
-                        is_esc = true;
-                    }
-                    continue;
-                } else {
-                    is_esc = false;
-                    cur_text.push(c);
+                if depth == 0 {
+                    return Ok(i - 1);
                 }
-
-                continue;
             }
+            _ => i += 1,
+        }
+    }
 
-            if c == '/' {
-                if let Some((_, c2)) = chars.peek() {
-                    if *c2 == '/' {
-                        break;
-                    }
+    Err(error::Error::new(
+        error::LexerErrorType::NonTerminatedComment,
+        span(&chars[start], &chars[chars.len() - 1]),
+    ))
+}
+
+/// Lexes a string literal starting with the `"` at index `start`. Strings can span multiple lines.
+/// Returns the token together with the index of the closing `"`. Invalid escape sequences are
+/// added to `errors` and the rest of the string is still lexed.
+///
+/// # Example
+/// ```text
+/// "Hello,
+/// world!\n"
+/// ```
+fn string(
+    chars: &[Char],
+    start: usize,
+    errors: &mut Vec<error::Error<error::LexerErrorType>>,
+) -> Result<(Token, usize), error::Error<error::LexerErrorType>> {
+    let mut value = String::new();
+    let mut i = start + 1;
+
+    loop {
+        match chars.get(i) {
+            Some((_, _, '"')) => break,
+            Some((_, _, '\\')) => match parse_escape(chars, i) {
+                Ok((c, end)) => {
+                    value.push(c);
+                    i = end + 1;
+                }
+                Err(e) => {
+                    errors.push(e);
+                    i += 1;
                 }
+            },
+            Some((_, _, c)) => {
+                value.push(*c);
+                i += 1;
             }
+            None => return Err(non_terminated_string(chars, start)),
+        }
+    }
 
-            let c_is_white_space = c == ' ' || c == '\t';
+    let token = parse_token(&value, Eval::String, span(&chars[start], &chars[i]))?;
 
-            if !c_is_white_space
-                && cur_eval == Some(Eval::Number)
-                && Eval::from_char(c) == Some(Eval::Symbol)
-            {
-                if let Ok(token) = parse_token(&format!("{}", c), Eval::Symbol, ln, cl, cl) {
-                    if token.token_type == TokenType::Dot {
-                        cur_text.push(c);
+    Ok((token, i))
+}
 
-                        continue;
-                    }
-                }
-            }
+/// Lexes a raw string starting with the `r` at index `start`. Raw strings don't have escape
+/// sequences, to be able to use `"` in them they can be surrounded by any number of `#`. Returns
+/// the token together with the index of the last character of the string.
+///
+/// # Example
+/// ```text
+/// r"C:\Users"
+/// r#"She said "hi""#
+/// ```
+fn raw_string(
+    chars: &[Char],
+    start: usize,
+) -> Result<(Token, usize), error::Error<error::LexerErrorType>> {
+    let hashes = take_while(chars, start + 1, |c| c == '#') - (start + 1);
+    let quote = start + 1 + hashes;
+
+    match chars.get(quote) {
+        Some((_, _, '"')) => {}
+        Some(char) => {
+            return Err(error::Error::new(
+                error::LexerErrorType::UnexpectedSymbol(char.2.to_string()),
+                span(char, char),
+            ));
+        }
+        None => return Err(non_terminated_string(chars, start)),
+    }
 
-            if c_is_white_space
-                || (Eval::from_char(c) != cur_eval && Eval::from_char(c) != Some(Eval::Symbol))
-            {
-                if let Some(e) = cur_eval {
-                    tokens.push(parse_token(&cur_text, e, ln, cl_start, cl - 1)?);
+    let closing = std::iter::once('"')
+        .chain(std::iter::repeat_n('#', hashes))
+        .collect::<Vec<_>>();
 
-                    cur_eval = Eval::from_char(c);
-                    cur_text.clear();
-                    if cur_eval != None {
-                        cur_text.push(c);
-                    }
+    let mut i = quote + 1;
 
-                    continue;
-                }
+    while !chars[i.min(chars.len())..]
+        .iter()
+        .map(|(_, _, c)| *c)
+        .take(closing.len())
+        .eq(closing.iter().copied())
+    {
+        if i >= chars.len() {
+            return Err(non_terminated_string(chars, start));
+        }
 
-                cur_eval = None;
-            }
+        i += 1;
+    }
 
-            if !c_is_white_space {
-                match Eval::from_char(c) {
-                    Some(Eval::Symbol) => {
-                        if let Some(e) = cur_eval {
-                            tokens.push(parse_token(&cur_text, e, ln, cl_start, cl - 1)?);
-                            cur_text.clear();
-                        }
+    let value = chars[quote + 1..i]
+        .iter()
+        .map(|(_, _, c)| c)
+        .collect::<String>();
+    let end = i + hashes;
 
-                        let symbol = format!(
-                            "{}{}",
-                            c,
-                            chars
-                                .peek()
-                                .map(|(_, c)| c.to_string())
-                                .unwrap_or("SOMETHING_THAT_WILL_NEVER_MATCH".to_string())
-                        );
-
-                        if let Ok(token) = parse_token(&symbol, Eval::Symbol, ln, cl, cl + 1) {
-                            tokens.push(token);
-                            chars.next();
-                        } else {
-                            tokens.push(parse_token(&format!("{}", c), Eval::Symbol, ln, cl, cl)?);
-                        }
+    let token = parse_token(&value, Eval::String, span(&chars[start], &chars[end]))?;
 
-                        cur_eval = None;
-                    }
-                    None => {
-                        return Err(error::Error::new(
-                            error::LexerErrorType::UnexpectedSymbol(c.to_string()),
-                            ln + 1,
-                            cl + 1,
-                            ln + 1,
-                            cl + 1,
-                        ));
-                    }
-                    _ => {
-                        if Eval::from_char(c) != cur_eval {
-                            cur_eval = Eval::from_char(c);
-                            cl_start = cl;
-                            cur_text.clear();
-                        }
-                        cur_text.push(c);
-                    }
+    Ok((token, end))
+}
+
+/// The error for a string starting at index `start` that isn't closed before the end of the code
+fn non_terminated_string(chars: &[Char], start: usize) -> error::Error<error::LexerErrorType> {
+    error::Error::new(
+        error::LexerErrorType::NonTerminatedString,
+        span(&chars[start], &chars[chars.len() - 1]),
+    )
+}
+
+/// Parses the escape sequence starting with the `\` at index `start` in a string literal. Returns
+/// the character it stands for together with the index of the last character of the sequence.
+///
+/// # Examples
+/// ```text
+/// \n \t \r \0 \\ \"
+/// \u{1F980}
+/// ```
+fn parse_escape(
+    chars: &[Char],
+    start: usize,
+) -> Result<(char, usize), error::Error<error::LexerErrorType>> {
+    let mut sequence = String::from("\\");
+    let mut end = start;
+
+    match chars.get(start + 1).map(|(_, _, c)| *c) {
+        Some('n') => return Ok(('\n', start + 1)),
+        Some('t') => return Ok(('\t', start + 1)),
+        Some('r') => return Ok(('\r', start + 1)),
+        Some('0') => return Ok(('\0', start + 1)),
+        Some('\\') => return Ok(('\\', start + 1)),
+        Some('"') => return Ok(('"', start + 1)),
+        Some('u') => {
+            sequence.push('u');
+            end = start + 1;
+
+            // The closing quote of the string is never part of the escape sequence
+            while let Some((_, _, c)) = chars.get(end + 1) {
+                if *c == '"' {
+                    break;
+                }
+
+                sequence.push(*c);
+                end += 1;
+
+                if *c == '}' {
+                    break;
                 }
             }
-        }
 
-        if cur_eval == Some(Eval::String) {
-            let ln = ln + 1;
-            let cl_start = cl_start + 1;
+            let code_point = sequence
+                .strip_prefix("\\u{")
+                .and_then(|s| s.strip_suffix('}'))
+                .filter(|hex| (1..=6).contains(&hex.len()))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .and_then(char::from_u32);
 
-            return Err(error::Error::new(
-                error::LexerErrorType::NonTerminatedString,
-                ln,
-                cl_start,
-                ln,
-                line.len(),
-            ));
+            if let Some(c) = code_point {
+                return Ok((c, end));
+            }
         }
-
-        if let Some(e) = cur_eval {
-            tokens.push(parse_token(&cur_text, e, ln, cl_start, line.len() - 1)?);
+        Some(c) => {
+            sequence.push(c);
+            end = start + 1;
         }
+        None => {}
+    }
+
+    Err(error::Error::new(
+        error::LexerErrorType::InvalidEscape(sequence),
+        span(&chars[start], &chars[end]),
+    ))
+}
+
+/// Returns the index after the last character of the number starting at index `start`. Letters
+/// are included so that a number like `0xFF` or `1e9` is a single token, and invalid numbers such
+/// as `12ab` are reported as such.
+fn number_end(chars: &[Char], start: usize) -> usize {
+    let is_part = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let get = |i: usize| chars.get(i).map(|(_, _, c)| *c);
+
+    let mut end = take_while(chars, start, is_part);
+    let is_decimal = !(get(start) == Some('0') && matches!(get(start + 1), Some('x' | 'b' | 'o')));
+
+    // A `.` is only part of the number if it's followed by a digit, so that `a.0.b` isn't lexed
+    // as a float
+    if is_decimal && get(end) == Some('.') && get(end + 1).is_some_and(is_number_char) {
+        end = take_while(chars, end + 1, is_part);
+    }
+
+    // The sign of an exponent, e.g. `1e-9`
+    if is_decimal
+        && matches!(get(end - 1), Some('e' | 'E'))
+        && matches!(get(end), Some('+' | '-'))
+        && get(end + 1).is_some_and(is_number_char)
+    {
+        end = take_while(chars, end + 1, is_part);
     }
 
-    Ok(tokens)
+    end
+}
+
+/// Parses the text of a number literal. Integers can be written in decimal, hexadecimal (`0x`),
+/// binary (`0b`) or octal (`0o`), floats have a fraction and/or an exponent. Underscores can be
+/// used to separate digits.
+///
+/// # Examples
+/// ```text
+/// 1_000_000 0xFF 0b1010 0o777
+/// 3.14 1e-9 2.5E3
+/// ```
+fn parse_number(word: &str) -> Result<TokenType, error::LexerErrorType> {
+    let invalid = || error::LexerErrorType::InvalidNumber(word.to_string());
+    let digits = word.replace('_', "");
+
+    let radix = match digits.get(..2) {
+        Some("0x") => Some(16),
+        Some("0b") => Some(2),
+        Some("0o") => Some(8),
+        _ => None,
+    };
+
+    if let Some(radix) = radix {
+        return match i64::from_str_radix(&digits[2..], radix) {
+            Ok(num) => Ok(TokenType::IntegerLiteral(num)),
+            Err(e) if *e.kind() == std::num::IntErrorKind::PosOverflow => {
+                Err(error::LexerErrorType::NumberOverflow(word.to_string()))
+            }
+            Err(_) => Err(invalid()),
+        };
+    }
+
+    if digits.contains(['.', 'e', 'E']) {
+        return match digits.parse::<f64>() {
+            Ok(num) if num.is_finite() => Ok(TokenType::FloatLiteral(num)),
+            Ok(_) => Err(error::LexerErrorType::NumberOverflow(word.to_string())),
+            Err(_) => Err(invalid()),
+        };
+    }
+
+    match digits.parse::<i64>() {
+        Ok(num) => Ok(TokenType::IntegerLiteral(num)),
+        Err(e) if *e.kind() == std::num::IntErrorKind::PosOverflow => {
+            Err(error::LexerErrorType::NumberOverflow(word.to_string()))
+        }
+        Err(_) => Err(invalid()),
+    }
 }
 
+/// Creates a token from the text of it, the span is where the token is in the code
 fn parse_token(
     word: &str,
     eval: Eval,
-    ln: usize,
-    cl_start: usize,
-    cl_end: usize,
+    span: span::Span,
 ) -> Result<Token, error::Error<error::LexerErrorType>> {
     let token_type = match eval {
         Eval::Word => match word {
@@ -257,23 +576,15 @@ fn parse_token(
             "false" => TokenType::BooleanLiteral(false),
             "out" => TokenType::Out,
             "ns" => TokenType::Namespace,
-            "__COMPILER_SET_CUSTOM_PRE_DEFINED" => {
-                TokenType::CompilerSetCustomCodePreDefined("".to_string())
-            }
+            "intrinsic" => TokenType::Intrinsic,
+            "extern" => TokenType::Extern,
             string => TokenType::Identifier(string.to_string()),
         },
         Eval::Symbol => {
-            if !SYMBOLS.iter().any(|s| *s == word) {
-                let ln = ln + 1;
-                let cl_start = cl_start + 1;
-                let cl_end = cl_end + 1;
-
+            if !SYMBOLS.contains(&word) {
                 return Err(error::Error::new(
                     error::LexerErrorType::UnexpectedSymbol(word.to_string()),
-                    ln,
-                    cl_start,
-                    ln,
-                    cl_end,
+                    span,
                 ));
             }
 
@@ -299,6 +610,10 @@ fn parse_token(
                 "=" => TokenType::Assign,
                 "+=" => TokenType::AddAssign,
                 "-=" => TokenType::SubAssign,
+                "*=" => TokenType::MulAssign,
+                "/=" => TokenType::DivAssign,
+                "%=" => TokenType::ModAssign,
+                "**=" => TokenType::PowAssign,
                 "==" => TokenType::Equal,
                 "!=" => TokenType::NotEqual,
                 "<" => TokenType::LessThan,
@@ -311,39 +626,16 @@ fn parse_token(
                 _ => unreachable!(),
             }
         }
-        Eval::Number => {
-            if let Ok(num) = word.parse::<i64>() {
-                TokenType::IntegerLiteral(num)
-            } else if let Ok(num) = word.parse::<f64>() {
-                TokenType::FloatLiteral(num)
-            } else {
-                let ln = ln + 1;
-                let cl_start = cl_start + 1;
-                let cl_end = cl_end + 1;
-
-                return Err(error::Error::new(
-                    error::LexerErrorType::InvalidNumber(word.to_string()),
-                    ln,
-                    cl_start,
-                    ln,
-                    cl_end,
-                ));
+        Eval::Number => match parse_number(word) {
+            Ok(token_type) => token_type,
+            Err(error_type) => {
+                return Err(error::Error::new(error_type, span));
             }
-        }
+        },
         Eval::String => TokenType::StringLiteral(word.to_string()),
     };
 
-    let ln = ln + 1;
-    let cl_start = cl_start + 1;
-    let cl_end = cl_end + 1;
-
-    Ok(Token {
-        token_type,
-        ln_start: ln,
-        cl_start,
-        ln_end: ln,
-        cl_end,
-    })
+    Ok(Token { token_type, span })
 }
 
 #[cfg(test)]
@@ -363,4 +655,269 @@ mod tests {
             assert_eq!(tokens[i].token_type, token_types[i]);
         }
     }
+
+    #[test]
+    fn function_declaration() {
+        use TokenType::*;
+
+        let tokens = tokenize("fnc sum(a: int, out b: int) -> int {}", 0).0;
+
+        assert_tokens(
+            tokens,
+            vec![
+                Function,
+                Identifier("sum".to_string()),
+                ParenOpen,
+                Identifier("a".to_string()),
+                Colon,
+                Integer,
+                Comma,
+                Out,
+                Identifier("b".to_string()),
+                Colon,
+                Integer,
+                ParenClose,
+                RightArrow,
+                Integer,
+                CurlyOpen,
+                CurlyClose,
+            ],
+        );
+    }
+
+    #[test]
+    fn string_escapes() {
+        let tokens = tokenize(r#""a\n\t\r\0\\\"\u{1F980}""#, 0).0;
+
+        assert_tokens(
+            tokens,
+            vec![TokenType::StringLiteral(
+                "a\n\t\r\0\\\"\u{1F980}".to_string(),
+            )],
+        );
+
+        for code in [r#""\q""#, r#""\u{}""#, r#""\u{110000}""#, r#""\u{41""#] {
+            assert!(matches!(
+                tokenize(code, 0).1[0].error_type(),
+                error::LexerErrorType::InvalidEscape(..)
+            ));
+        }
+    }
+
+    #[test]
+    fn crlf_and_bom() {
+        use TokenType::*;
+
+        let code = "\u{feff}fnc main() {\r\n    ret;\r\n}\r\n";
+        let mut source_map = span::SourceMap::new();
+        let file_id = source_map.add_file("main.zy", code);
+        let tokens = tokenize(code, file_id).0;
+
+        assert_eq!(source_map.location(tokens[0].span), "main.zy ln: 1 cl: 1-3");
+        assert_eq!(source_map.location(tokens[5].span), "main.zy ln: 2 cl: 5-7");
+        assert_tokens(
+            tokens,
+            vec![
+                Function,
+                Identifier("main".to_string()),
+                ParenOpen,
+                ParenClose,
+                CurlyOpen,
+                Return,
+                Semicolon,
+                CurlyClose,
+            ],
+        );
+    }
+
+    #[test]
+    fn multi_line_string() {
+        let code = "a = \"one\r\n  two\";";
+        let mut source_map = span::SourceMap::new();
+        let file_id = source_map.add_file("main.zy", code);
+        let tokens = tokenize(code, file_id).0;
+
+        assert_eq!(
+            tokens[2].token_type,
+            TokenType::StringLiteral("one\n  two".to_string())
+        );
+        assert_eq!(
+            source_map.location(tokens[2].span),
+            "main.zy ln: 1-2 cl: 5-6"
+        );
+        assert_eq!(source_map.location(tokens[3].span), "main.zy ln: 2 cl: 7");
+
+        assert!(matches!(
+            tokenize("a = \"one\n two", 0).1[0].error_type(),
+            error::LexerErrorType::NonTerminatedString
+        ));
+    }
+
+    #[test]
+    fn raw_strings() {
+        let tokens = tokenize(r###"r"C:\Users" r#"say "hi""# r##"a"#b"##"###, 0).0;
+
+        assert_tokens(
+            tokens,
+            vec![
+                TokenType::StringLiteral(r"C:\Users".to_string()),
+                TokenType::StringLiteral(r#"say "hi""#.to_string()),
+                TokenType::StringLiteral(r##"a"#b"##.to_string()),
+            ],
+        );
+
+        assert!(matches!(
+            tokenize(r##"r#"a""##, 0).1[0].error_type(),
+            error::LexerErrorType::NonTerminatedString
+        ));
+    }
+
+    #[test]
+    fn comments() {
+        use TokenType::*;
+
+        let tokens = tokenize(
+            "a /* one /* two */ still\n a comment */ b // line\n\
+             /// Doc\n\
+             ///No space\n\
+             //// Not a doc comment\n\
+             c",
+            0,
+        )
+        .0;
+
+        assert_tokens(
+            tokens,
+            vec![
+                Identifier("a".to_string()),
+                Identifier("b".to_string()),
+                DocComment("Doc".to_string()),
+                DocComment("No space".to_string()),
+                Identifier("c".to_string()),
+            ],
+        );
+
+        assert!(matches!(
+            tokenize("a /* one /* two */", 0).1[0].error_type(),
+            error::LexerErrorType::NonTerminatedComment
+        ));
+    }
+
+    #[test]
+    fn numbers() {
+        use TokenType::*;
+
+        let tokens = tokenize("1_000_000 0xFF 0b1010 0o777 3.5 1e-9 2.5E3 1e+2 a.0.b", 0).0;
+
+        assert_tokens(
+            tokens,
+            vec![
+                IntegerLiteral(1_000_000),
+                IntegerLiteral(0xFF),
+                IntegerLiteral(0b1010),
+                IntegerLiteral(0o777),
+                FloatLiteral(3.5),
+                FloatLiteral(1e-9),
+                FloatLiteral(2.5e3),
+                FloatLiteral(1e2),
+                Identifier("a".to_string()),
+                Dot,
+                IntegerLiteral(0),
+                Dot,
+                Identifier("b".to_string()),
+            ],
+        );
+
+        for code in ["12ab", "0x", "0b102", "1e"] {
+            assert!(matches!(
+                tokenize(code, 0).1[0].error_type(),
+                error::LexerErrorType::InvalidNumber(..)
+            ));
+        }
+
+        for code in ["9223372036854775808", "0xFFFFFFFFFFFFFFFF", "1e999"] {
+            assert!(matches!(
+                tokenize(code, 0).1[0].error_type(),
+                error::LexerErrorType::NumberOverflow(..)
+            ));
+        }
+    }
+
+    #[test]
+    fn identifiers() {
+        use TokenType::*;
+
+        let tokens = tokenize("x1 vec2 utf8 _0 größe 变量 a_b", 0).0;
+
+        assert_tokens(
+            tokens,
+            ["x1", "vec2", "utf8", "_0", "größe", "变量", "a_b"]
+                .iter()
+                .map(|s| Identifier(s.to_string()))
+                .collect(),
+        );
+        assert!(matches!(
+            tokenize("a€", 0).1[0].error_type(),
+            error::LexerErrorType::UnexpectedSymbol(..)
+        ));
+    }
+
+    #[test]
+    fn assignment_operators() {
+        use TokenType::*;
+
+        let tokens = tokenize("a = b += c -= d *= e /= f %= g **= h ** i", 0).0;
+
+        assert_tokens(
+            tokens
+                .into_iter()
+                .filter(|t| !t.token_type.shallow_eq(&Identifier("".to_string())))
+                .collect(),
+            vec![
+                Assign, AddAssign, SubAssign, MulAssign, DivAssign, ModAssign, PowAssign, Pow,
+            ],
+        );
+    }
+
+    #[test]
+    fn error_recovery() {
+        use TokenType::*;
+
+        let (tokens, errors) = tokenize("a $ b = 12ab + \"\\q\"; c = \"unterminated", 0);
+
+        assert_tokens(
+            tokens,
+            vec![
+                Identifier("a".to_string()),
+                Error,
+                Identifier("b".to_string()),
+                Assign,
+                Error,
+                Add,
+                StringLiteral("q".to_string()),
+                Semicolon,
+                Identifier("c".to_string()),
+                Assign,
+                Error,
+            ],
+        );
+
+        assert_eq!(errors.len(), 4);
+        assert!(matches!(
+            errors[0].error_type(),
+            error::LexerErrorType::UnexpectedSymbol(..)
+        ));
+        assert!(matches!(
+            errors[1].error_type(),
+            error::LexerErrorType::InvalidNumber(..)
+        ));
+        assert!(matches!(
+            errors[2].error_type(),
+            error::LexerErrorType::InvalidEscape(..)
+        ));
+        assert!(matches!(
+            errors[3].error_type(),
+            error::LexerErrorType::NonTerminatedString
+        ));
+    }
 }
@@ -15,7 +15,8 @@ pub enum TokenType {
     Struct,
     Out,
     Namespace,
-    CompilerSetCustomCodePreDefined(String),
+    Intrinsic,
+    Extern,
 
     Integer,
     Float,
@@ -48,6 +49,10 @@ pub enum TokenType {
     Assign,
     AddAssign,
     SubAssign,
+    MulAssign,
+    DivAssign,
+    ModAssign,
+    PowAssign,
 
     Equal,
     NotEqual,
@@ -64,7 +69,11 @@ pub enum TokenType {
     IntegerLiteral(i64),
     FloatLiteral(f64),
     StringLiteral(String),
+    DocComment(String),
     BooleanLiteral(bool),
+
+    // Code that couldn't be lexed, the lexer reports the error
+    Error,
 }
 
 impl std::fmt::Display for TokenType {
@@ -76,15 +85,18 @@ impl std::fmt::Display for TokenType {
             TokenType::If => write!(f, "if"),
             TokenType::Else => write!(f, "else"),
             TokenType::Return => write!(f, "ret"),
-            TokenType::Break => write!(f, "brk"),
-            TokenType::Continue => write!(f, "cnt"),
-            TokenType::While => write!(f, "whl"),
-            TokenType::Struct => write!(f, "str"),
+            TokenType::Break => write!(f, "break"),
+            TokenType::Continue => write!(f, "continue"),
+            TokenType::While => write!(f, "while"),
+            TokenType::Struct => write!(f, "struct"),
             TokenType::Out => write!(f, "out"),
             TokenType::Namespace => write!(f, "ns"),
-            TokenType::CompilerSetCustomCodePreDefined(code) => {
-                write!(f, "CompilerSetCustomCodePreDefined({})", code)
-            }
+            TokenType::Intrinsic => write!(f, "intrinsic"),
+            TokenType::Extern => write!(f, "extern"),
+            TokenType::Integer => write!(f, "int"),
+            TokenType::Float => write!(f, "flt"),
+            TokenType::Boolean => write!(f, "bln"),
+            TokenType::String => write!(f, "str"),
 
             TokenType::Add => write!(f, "+"),
             TokenType::Sub => write!(f, "-"),
@@ -111,6 +123,10 @@ impl std::fmt::Display for TokenType {
             TokenType::Assign => write!(f, "="),
             TokenType::AddAssign => write!(f, "+="),
             TokenType::SubAssign => write!(f, "-="),
+            TokenType::MulAssign => write!(f, "*="),
+            TokenType::DivAssign => write!(f, "/="),
+            TokenType::ModAssign => write!(f, "%="),
+            TokenType::PowAssign => write!(f, "**="),
 
             TokenType::Equal => write!(f, "=="),
             TokenType::NotEqual => write!(f, "!="),
@@ -123,6 +139,7 @@ impl std::fmt::Display for TokenType {
             TokenType::Not => write!(f, "!"),
 
             TokenType::StringLiteral(string) => write!(f, "\"{}\"", string),
+            TokenType::DocComment(doc) => write!(f, "///{}", doc),
             TokenType::Identifier(string) => write!(f, "{}", string),
             TokenType::IntegerLiteral(num) => write!(f, "{}", num),
             TokenType::FloatLiteral(num) => {
@@ -133,7 +150,7 @@ impl std::fmt::Display for TokenType {
                 }
             }
             TokenType::BooleanLiteral(boolean) => write!(f, "{}", boolean),
-            _ => todo!(),
+            TokenType::Error => write!(f, "<error>"),
         }
     }
 }
@@ -142,26 +159,12 @@ impl TokenType {
     /// Returns if it's the same token type, ignoring the value
     pub fn shallow_eq(&self, other: &TokenType) -> bool {
         match self {
-            TokenType::Identifier(_) => match other {
-                TokenType::Identifier(_) => true,
-                _ => false,
-            },
-            TokenType::IntegerLiteral(_) => match other {
-                TokenType::IntegerLiteral(_) => true,
-                _ => false,
-            },
-            TokenType::FloatLiteral(_) => match other {
-                TokenType::FloatLiteral(_) => true,
-                _ => false,
-            },
-            TokenType::StringLiteral(_) => match other {
-                TokenType::StringLiteral(_) => true,
-                _ => false,
-            },
-            TokenType::BooleanLiteral(_) => match other {
-                TokenType::BooleanLiteral(_) => true,
-                _ => false,
-            },
+            TokenType::Identifier(_) => matches!(other, TokenType::Identifier(_)),
+            TokenType::IntegerLiteral(_) => matches!(other, TokenType::IntegerLiteral(_)),
+            TokenType::FloatLiteral(_) => matches!(other, TokenType::FloatLiteral(_)),
+            TokenType::StringLiteral(_) => matches!(other, TokenType::StringLiteral(_)),
+            TokenType::DocComment(_) => matches!(other, TokenType::DocComment(_)),
+            TokenType::BooleanLiteral(_) => matches!(other, TokenType::BooleanLiteral(_)),
             _ => self == other,
         }
     }
@@ -170,26 +173,11 @@ impl TokenType {
 #[derive(Clone, Debug)]
 pub struct Token {
     pub token_type: TokenType,
-    pub ln_start: usize,
-    pub cl_start: usize,
-    pub ln_end: usize,
-    pub cl_end: usize,
+    pub span: span::Span,
 }
 
-impl cl_ln::ClLn for Token {
-    fn cl_start(&self) -> usize {
-        self.cl_start
-    }
-
-    fn cl_end(&self) -> usize {
-        self.cl_end
-    }
-
-    fn ln_start(&self) -> usize {
-        self.ln_start
-    }
-
-    fn ln_end(&self) -> usize {
-        self.ln_end
+impl span::Spanned for Token {
+    fn span(&self) -> span::Span {
+        self.span
     }
 }
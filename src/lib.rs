@@ -1,7 +1,8 @@
-mod cl_ln;
 pub mod common;
 pub mod compiler;
 pub mod error;
+pub mod intrinsic;
 pub mod lexer;
 pub mod parser;
+pub mod span;
 pub mod static_analyzer;
@@ -1,42 +1,62 @@
-// const STD_LIBRARY: &str = include_str!("./std.zy");
+fn compile(code: &str, freestanding: bool) -> Result<String, Vec<String>> {
+    let mut source_map = zyrahn::span::SourceMap::new();
+    let file_id = source_map.add_file("main.zy", code);
 
-fn compile(code: &str) -> Result<String, Vec<Box<dyn std::error::Error>>> {
-    // let code = format!("{}\n\n{}", code, STD_LIBRARY);
+    // Parsing continues past lexical errors and the parts of the tree without syntax errors are
+    // still checked, so that every error in the code is reported at once
+    let (tokens, lexer_errs) = zyrahn::lexer::tokenize(code, file_id);
+    let (ast, parser_errs) = zyrahn::parser::gen(&tokens);
 
-    let tokens = zyrahn::lexer::tokenize(&code);
-    if let Err(e) = &tokens {
-        return Err(vec![Box::new(e.clone())]);
-    }
-    let tokens = tokens.unwrap();
+    // Freestanding builds don't load the standard library
+    let typed_ast = if freestanding {
+        zyrahn::static_analyzer::evaluate_freestanding(&ast)
+    } else {
+        zyrahn::static_analyzer::evaluate(&ast)
+    };
 
-    let ast = zyrahn::parser::gen(&tokens);
-    if let Err(e) = &ast {
-        return Err(vec![Box::new(e.clone())]);
+    let mut out_errs = vec![];
+    for e in lexer_errs {
+        out_errs.push(e.report(&source_map));
     }
-    let ast = ast.unwrap();
-
-    let typed_ast = zyrahn::static_analyzer::evaluate(&ast);
-
-    if let Err(errs) = typed_ast {
-        let mut out_errs: Vec<Box<dyn std::error::Error>> = vec![];
-        for e in errs {
-            out_errs.push(Box::new(e.clone()));
+    for e in parser_errs {
+        out_errs.push(e.report(&source_map));
+    }
+    if let Err(errs) = &typed_ast {
+        // Syntax errors have already been reported by the parser
+        for e in errs.iter().filter(|e| {
+            !matches!(
+                e.error_type(),
+                zyrahn::error::StaticAnalyzerErrorType::SyntaxError
+            )
+        }) {
+            out_errs.push(e.report(&source_map));
         }
+    }
+
+    if !out_errs.is_empty() {
         return Err(out_errs);
     }
 
-    Ok(zyrahn::compiler::javascript::compile(&typed_ast.unwrap()))
+    Ok(zyrahn::compiler::javascript::compile(
+        &typed_ast.unwrap(),
+        &source_map,
+    ))
 }
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+
+    let freestanding = args.len() == 3 && args[1] == "--freestanding";
+    if freestanding {
+        args.remove(1);
+    }
 
     if args.len() != 2 {
-        println!("Usage: {} <code>", args[0]);
+        println!("Usage: {} [--freestanding] <code>", args[0]);
         std::process::exit(1);
     }
 
-    let js = compile(&args[1]).unwrap_or_else(|errs| {
+    let js = compile(&args[1], freestanding).unwrap_or_else(|errs| {
         for e in errs {
             println!("{}", e);
         }
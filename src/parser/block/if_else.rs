@@ -0,0 +1,150 @@
+use super::*;
+
+/// Parses an if statement together with all of its `else if` and `else` branches
+///
+/// Assumes that the first token is `if`. Returns the node and how many tokens it consumed. An
+/// `else if` is an if statement in the `else` body, but the branches are parsed in a loop at the
+/// same depth, so that a long chain of them isn't nested too deep.
+///
+/// # Example
+/// ```text
+/// if a == 3 {
+///     b = 4;
+/// } else if a == 4 {
+///     b = 5;
+/// } else {
+///     b = 6;
+/// }
+/// ```
+pub fn gen(
+    tokens: &[lexer::Token],
+    depth: usize,
+    errors: &mut Vec<error::Error<error::ParserErrorType>>,
+) -> Result<(node::BlockNode<node::expression::All>, usize), error::Error<error::ParserErrorType>> {
+    check_depth(depth + 1, &tokens[0])?;
+
+    // Where the `if` of every branch is, together with its condition and body
+    let mut branches = vec![];
+    let mut start = 0;
+
+    let (mut else_body, consumed) = loop {
+        let (cond, then_body, body_end) = branch(&tokens[start..], depth, errors)?;
+        let body_end = start + body_end;
+        branches.push((start, cond, then_body));
+
+        if tokens.get(body_end + 1).map(|t| &t.token_type) != Some(&lexer::TokenType::Else) {
+            break (None, body_end + 1);
+        }
+
+        let else_start = body_end + 2;
+
+        match tokens.get(else_start) {
+            Some(lexer::Token {
+                token_type: lexer::TokenType::If,
+                ..
+            }) => start = else_start,
+            Some(lexer::Token {
+                token_type: lexer::TokenType::CurlyOpen,
+                ..
+            }) => {
+                let else_end = else_start + curly_end(&tokens[else_start..])?;
+
+                break (
+                    Some(super::gen(
+                        &tokens[else_start + 1..else_end],
+                        depth + 1,
+                        errors,
+                    )),
+                    else_end + 1,
+                );
+            }
+            Some(t) => {
+                return Err(error::Error::from_span(
+                    error::ParserErrorType::UnexpectedTokenExpected(
+                        t.token_type.clone(),
+                        lexer::TokenType::CurlyOpen,
+                    ),
+                    t,
+                ));
+            }
+            None => {
+                return Err(error::Error::from_span(
+                    error::ParserErrorType::StatementEndEarly,
+                    &tokens[body_end + 1],
+                ));
+            }
+        }
+    };
+
+    // The branches are put together from the last one, every `else if` is the only statement in
+    // the `else` body of the branch before it
+    let mut statement = None;
+
+    for (start, cond, then_body) in branches.into_iter().rev() {
+        let node = match statement
+            .take()
+            .map(|else_if| vec![else_if])
+            .or(else_body.take())
+        {
+            Some(else_body) => node::block::All::IfElse {
+                cond,
+                then_body,
+                else_body,
+            },
+            None => node::block::All::If { cond, then_body },
+        };
+
+        statement = Some(Node::from_span(
+            node,
+            &span::combine(&tokens[start..consumed]),
+        ));
+    }
+
+    Ok((
+        statement.expect("An if statement has at least one branch"),
+        consumed,
+    ))
+}
+
+/// The condition and the body of a branch, together with the index of the `}` ending the body
+type Branch = (
+    Node<node::expression::All>,
+    Vec<node::BlockNode<node::expression::All>>,
+    usize,
+);
+
+/// Parses the condition and the body of a single branch, assumes that the first token is `if`
+fn branch(
+    tokens: &[lexer::Token],
+    depth: usize,
+    errors: &mut Vec<error::Error<error::ParserErrorType>>,
+) -> Result<Branch, error::Error<error::ParserErrorType>> {
+    let body_start = match tokens
+        .iter()
+        .position(|t| t.token_type == lexer::TokenType::CurlyOpen)
+    {
+        Some(i) => i,
+        None => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::StatementEndEarly,
+                &tokens[0],
+            ));
+        }
+    };
+
+    let cond_tokens = &tokens[1..body_start];
+
+    if cond_tokens.is_empty() {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::EmptyExpression,
+            &span::combine(&tokens[..=body_start]),
+        ));
+    }
+
+    let cond = expression::gen(cond_tokens)?;
+
+    let body_end = body_start + curly_end(&tokens[body_start..])?;
+    let then_body = super::gen(&tokens[body_start + 1..body_end], depth + 1, errors);
+
+    Ok((cond, then_body, body_end))
+}
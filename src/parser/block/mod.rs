@@ -1,13 +1,22 @@
 use super::{node::Node, *};
-use cl_ln::ClLn;
+use span::Spanned;
 
-mod r#type;
-mod var_dec;
+mod if_else;
+pub(super) mod r#type;
+mod var_assign;
+pub(super) mod var_dec;
+mod while_loop;
 
-/// Generates an abstract syntax tree from a list of tokens for the block syntax.
+/// Generates an abstract syntax tree from a list of tokens for the block syntax. The statements are
+/// parsed one after another, only the bodies of `if` and `while` are parsed recursively. The depth
+/// is how many blocks, namespaces included, the tokens are nested in.
+///
+/// A statement that fails to parse is replaced by an `Error` node and its error is added to
+/// `errors`, unless the lexer already reported an error in it. Parsing then continues with the next
+/// statement.
 ///
 /// # Examples
-/// ```
+/// ```text
 /// var a = 3;
 ///
 /// if a == 3 {
@@ -16,60 +25,630 @@ mod var_dec;
 /// ```
 pub fn gen(
     tokens: &[lexer::Token],
-) -> Result<
-    Vec<Node<node::block::All<Node<node::expression::All>>>>,
-    error::Error<error::ParserErrorType>,
-> {
-    if tokens.len() == 0 {
-        return Ok(vec![]);
+    depth: usize,
+    errors: &mut Vec<error::Error<error::ParserErrorType>>,
+) -> node::Block<node::expression::All> {
+    let mut nodes = vec![];
+    let mut start = 0;
+
+    while start < tokens.len() {
+        let tokens = &tokens[start..];
+
+        // An empty statement, e.g. a `;` after the body of an if statement
+        if tokens[0].token_type == lexer::TokenType::Semicolon {
+            start += 1;
+            continue;
+        }
+
+        match statement(tokens, depth, errors) {
+            Ok((node, consumed)) => {
+                nodes.push(node);
+                start += consumed;
+            }
+            Err(error) => {
+                let consumed = synchronize(tokens);
+
+                nodes.push(Node::from_span(
+                    node::block::All::Error {},
+                    &span::combine(&tokens[..consumed]),
+                ));
+                if !has_lexer_error(&tokens[..consumed]) {
+                    errors.push(locate_error(error, &tokens[..consumed]));
+                }
+                start += consumed;
+            }
+        }
     }
 
+    nodes
+}
+
+/// Parses the first statement in the tokens. Returns the node and how many tokens it consumed,
+/// including the `;` at the end if the statement has one. Errors in the bodies of the statement
+/// are added to `errors`.
+fn statement(
+    tokens: &[lexer::Token],
+    depth: usize,
+    errors: &mut Vec<error::Error<error::ParserErrorType>>,
+) -> Result<(node::BlockNode<node::expression::All>, usize), error::Error<error::ParserErrorType>> {
     match &tokens[0].token_type {
-        lexer::TokenType::Var => {
-            for i in 0..tokens.len() {
-                if tokens[i].token_type == lexer::TokenType::Semicolon {
-                    let var_dec_tokens = &tokens[0..i];
-                    let rest_tokens = &tokens[i + 1..];
+        lexer::TokenType::DocComment(_) => Err(error::Error::from_span(
+            error::ParserErrorType::MisplacedDocComment,
+            &tokens[0],
+        )),
+        lexer::TokenType::If => if_else::gen(tokens, depth, errors),
+        lexer::TokenType::While => while_loop::gen(tokens, depth, errors),
+        lexer::TokenType::Break | lexer::TokenType::Continue => {
+            match tokens.get(1) {
+                Some(lexer::Token {
+                    token_type: lexer::TokenType::Semicolon,
+                    ..
+                }) => {}
+                Some(t) => {
+                    return Err(error::Error::from_span(
+                        error::ParserErrorType::UnexpectedTokenExpected(
+                            t.token_type.clone(),
+                            lexer::TokenType::Semicolon,
+                        ),
+                        t,
+                    ));
+                }
+                None => {
+                    return Err(error::Error::from_span(
+                        error::ParserErrorType::MissingSemicolon,
+                        &tokens[0],
+                    ));
+                }
+            }
+
+            let node = match tokens[0].token_type {
+                lexer::TokenType::Break => node::block::All::Break {},
+                _ => node::block::All::Continue {},
+            };
+
+            Ok((Node::from_span(node, &tokens[0]), 2))
+        }
+        lexer::TokenType::Return => {
+            let end = statement_end(tokens)?;
+            let value_tokens = &tokens[1..end];
+
+            let value = if value_tokens.is_empty() {
+                None
+            } else {
+                Some(expression::gen(value_tokens)?)
+            };
+
+            Ok((
+                Node::from_span(
+                    node::block::All::Return { value },
+                    &span::combine(&tokens[..end]),
+                ),
+                end + 1,
+            ))
+        }
+        lexer::TokenType::Var | lexer::TokenType::Const => {
+            let end = statement_end(tokens)?;
+
+            Ok((var_dec::gen(&tokens[..end])?, end + 1))
+        }
+        _ => {
+            let end = statement_end(tokens)?;
+            let expression_tokens = &tokens[..end];
+
+            if expression_tokens
+                .iter()
+                .any(|t| var_assign::is_assign(&t.token_type))
+            {
+                return Ok((var_assign::gen(expression_tokens)?, end + 1));
+            }
+
+            let expression = expression::gen(expression_tokens)?;
+            let span = expression.span();
+
+            Ok((
+                Node::from_span(node::block::All::Expression { value: expression }, &span),
+                end + 1,
+            ))
+        }
+    }
+}
+
+/// Finds where a statement that failed to parse ends, so that parsing can continue after it. That's
+/// after the `;` ending it, after the `}` ending its body if it's an if statement or a loop, or at
+/// the next keyword that starts a statement. Only curly brackets are counted, since they are the
+/// only brackets that can contain statements.
+fn synchronize(tokens: &[lexer::Token]) -> usize {
+    use lexer::TokenType::*;
 
-                    let var_dec = var_dec::gen(var_dec_tokens)?;
-                    let mut rest = gen(rest_tokens)?;
-                    rest.insert(0, var_dec);
+    let has_body = matches!(tokens[0].token_type, If | While);
+    let has_else = tokens[0].token_type == If;
+    let mut curly_count = 0usize;
 
-                    return Ok(rest);
+    for (i, t) in tokens.iter().enumerate().skip(1) {
+        match t.token_type {
+            CurlyOpen => curly_count += 1,
+            CurlyClose => {
+                curly_count = curly_count.saturating_sub(1);
+
+                let is_else_next = matches!(tokens.get(i + 1), Some(t) if t.token_type == Else);
+
+                if curly_count == 0 && has_body && !(has_else && is_else_next) {
+                    return i + 1;
                 }
             }
+            Semicolon if curly_count == 0 => return i + 1,
+            // `else if` continues the statement
+            If | While | Var | Const | Return | Break | Continue
+                if curly_count == 0 && tokens[i - 1].token_type != Else =>
+            {
+                return i;
+            }
+            _ => {}
+        }
+    }
+
+    tokens.len()
+}
+
+/// Finds the index of the `;` that ends the statement the tokens start with. Semicolons inside
+/// brackets belong to a nested statement and are skipped.
+fn statement_end(tokens: &[lexer::Token]) -> Result<usize, error::Error<error::ParserErrorType>> {
+    use lexer::TokenType::*;
+
+    // The brackets that are open, innermost last
+    let mut open: Vec<&lexer::Token> = vec![];
+
+    for (i, t) in tokens.iter().enumerate() {
+        let opening = match t.token_type {
+            CurlyOpen | SquareOpen | ParenOpen => {
+                open.push(t);
+                continue;
+            }
+            Semicolon if open.is_empty() => return Ok(i),
+            CurlyClose => CurlyOpen,
+            SquareClose => SquareOpen,
+            ParenClose => ParenOpen,
+            _ => continue,
+        };
 
-            return Err(error::Error::from_cl_ln(
-                error::ParserErrorType::MissingSemicolon,
-                &tokens[0],
+        if open.pop().map(|o| &o.token_type) != Some(&opening) {
+            return Err(error::Error::from_span(
+                match t.token_type {
+                    CurlyClose => error::ParserErrorType::UnexpectedCloseCurly,
+                    SquareClose => error::ParserErrorType::UnexpectedCloseSquare,
+                    _ => error::ParserErrorType::UnexpectedCloseParen,
+                },
+                t,
             ));
         }
-        _ => {
-            for i in 0..tokens.len() {
-                if tokens[i].token_type == lexer::TokenType::Semicolon {
-                    let expression_tokens = &tokens[0..i];
-                    let rest_tokens = &tokens[i + 1..];
-
-                    let expression = expression::gen(expression_tokens)?;
-                    let mut rest = gen(rest_tokens)?;
-                    let cl_ln = expression.cl_ln();
-
-                    rest.insert(
-                        0,
-                        Node::from_cl_ln(
-                            node::block::All::Expression { value: expression },
-                            &cl_ln,
-                        ),
-                    );
+    }
+
+    match open.first() {
+        Some(t) => Err(error::Error::from_span(
+            match t.token_type {
+                CurlyOpen => error::ParserErrorType::CurlyNotClosed,
+                SquareOpen => error::ParserErrorType::SquareNotClosed,
+                _ => error::ParserErrorType::ParenNotClosed,
+            },
+            *t,
+        )),
+        None => Err(error::Error::from_span(
+            error::ParserErrorType::MissingSemicolon,
+            &tokens[0],
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                    return Ok(rest);
+    fn parse(code: &str) -> Result<node::Block<node::expression::All>, error::ParserErrorType> {
+        let mut errors = vec![];
+        let tree = gen(&lexer::tokenize(code, 0).0, 0, &mut errors);
+
+        match errors.first() {
+            Some(error) => Err(error.error_type().clone()),
+            None => Ok(tree),
+        }
+    }
+
+    #[test]
+    fn else_if_chain() {
+        let tree = parse("if a { b; } else if c { d; } else { e; f; } g;").unwrap();
+
+        assert_eq!(tree.len(), 2);
+
+        match &tree[0].node {
+            node::block::All::IfElse {
+                then_body,
+                else_body,
+                ..
+            } => {
+                assert_eq!(then_body.len(), 1);
+                assert_eq!(else_body.len(), 1);
+
+                match &else_body[0].node {
+                    node::block::All::IfElse { else_body, .. } => assert_eq!(else_body.len(), 2),
+                    _ => panic!("Expected an else if"),
                 }
             }
+            _ => panic!("Expected an if else"),
+        }
+    }
 
-            return Err(error::Error::from_cl_ln(
-                error::ParserErrorType::MissingSemicolon,
-                &tokens[0],
+    #[test]
+    fn if_without_condition() {
+        assert!(matches!(
+            parse("if { a; }"),
+            Err(error::ParserErrorType::EmptyExpression)
+        ));
+    }
+
+    #[test]
+    fn empty_error_span() {
+        use span::Spanned;
+
+        let code = "a = 1;\nb = ;";
+        let tokens = lexer::tokenize(code, 1).0;
+        let error = locate_error(
+            empty_error(error::ParserErrorType::EmptyExpression),
+            &tokens[3..],
+        );
+
+        let mut source_map = span::SourceMap::new();
+        source_map.add_file("main.zy", code);
+
+        assert_eq!(source_map.location(error.span()), "main.zy ln: 2 cl: 5");
+    }
+
+    #[test]
+    fn else_without_body() {
+        assert!(matches!(
+            parse("if a { b; } else c;"),
+            Err(error::ParserErrorType::UnexpectedTokenExpected(
+                _,
+                lexer::TokenType::CurlyOpen
+            ))
+        ));
+    }
+
+    #[test]
+    fn while_loop() {
+        let tree = parse("while a { break; continue; } b;").unwrap();
+
+        assert_eq!(tree.len(), 2);
+
+        match &tree[0].node {
+            node::block::All::While { body, .. } => {
+                assert!(matches!(body[0].node, node::block::All::Break {}));
+                assert!(matches!(body[1].node, node::block::All::Continue {}));
+            }
+            _ => panic!("Expected a while loop"),
+        }
+    }
+
+    #[test]
+    fn break_without_semicolon() {
+        assert!(matches!(
+            parse("break"),
+            Err(error::ParserErrorType::MissingSemicolon)
+        ));
+    }
+
+    #[test]
+    fn compound_assignment() {
+        let tree = parse("a **= 2;").unwrap();
+
+        match &tree[0].node {
+            node::block::All::VariableAssignment {
+                target,
+                operator,
+                value,
+            } => {
+                assert!(matches!(
+                    &target.node,
+                    node::expression::All::SingleDataUnit {
+                        value: node::expression::SingleDataUnit::Identifier { identifier, .. }
+                    } if identifier == "a"
+                ));
+                assert_eq!(operator, &Some(lexer::TokenType::Pow));
+                assert!(matches!(
+                    value.node,
+                    node::expression::All::SingleDataUnit {
+                        value: node::expression::SingleDataUnit::Literal { .. }
+                    }
+                ));
+            }
+            _ => panic!("Expected a variable assignment"),
+        }
+    }
+
+    #[test]
+    fn field_assignment() {
+        let tree = parse("a.b.c = Point { x: 1, y: f(2) }.x;").unwrap();
+
+        match &tree[0].node {
+            node::block::All::VariableAssignment { target, .. } => assert!(matches!(
+                &target.node,
+                node::expression::All::SingleDataUnit {
+                    value: node::expression::SingleDataUnit::PropertyAccess { property, .. }
+                } if property == "c"
+            )),
+            _ => panic!("Expected a variable assignment"),
+        }
+
+        assert!(matches!(
+            parse("f() = 3;"),
+            Err(error::ParserErrorType::InvalidAssignmentTarget)
+        ));
+    }
+
+    #[test]
+    fn struct_init() {
+        let tree = parse("geo::Point { x: 1.0, y: 2.0, };").unwrap();
+
+        match &tree[0].node {
+            node::block::All::Expression {
+                value:
+                    Node {
+                        node:
+                            node::expression::All::SingleDataUnit {
+                                value:
+                                    node::expression::SingleDataUnit::StructInit {
+                                        namespace,
+                                        identifier,
+                                        values,
+                                    },
+                            },
+                        ..
+                    },
+            } => {
+                assert_eq!(namespace, &vec!["geo".to_string()]);
+                assert_eq!(identifier, "Point");
+                assert_eq!(values.len(), 2);
+            }
+            _ => panic!("Expected a struct init"),
+        }
+
+        assert!(matches!(
+            parse("Point { x: 1, x: 2 };"),
+            Err(error::ParserErrorType::DuplicateField(_))
+        ));
+    }
+
+    #[test]
+    fn array_init_and_access() {
+        let tree = parse("var a: [[int]] = [[1, 2], [3],]; a[0][1] = -a[1][0];").unwrap();
+
+        match &tree[0].node {
+            node::block::All::VariableDeclaration { ty, value, .. } => {
+                assert_eq!(
+                    ty,
+                    &Some(common::Type::Array(Box::new(common::Type::Array(
+                        Box::new(common::Type::Integer)
+                    ))))
+                );
+                assert!(matches!(
+                    &value.node,
+                    node::expression::All::SingleDataUnit {
+                        value: node::expression::SingleDataUnit::ArrayInit { values }
+                    } if values.len() == 2
+                ));
+            }
+            _ => panic!("Expected a variable declaration"),
+        }
+
+        assert!(matches!(
+            tree[1].node,
+            node::block::All::VariableAssignment { .. }
+        ));
+        assert!(matches!(
+            parse("var a: [int = [1];"),
+            Err(error::ParserErrorType::SquareNotClosed)
+        ));
+    }
+
+    #[test]
+    fn declaration_without_type() {
+        let tree = parse("var a = 3; cst b: int = 4;").unwrap();
+
+        assert!(matches!(
+            tree[0].node,
+            node::block::All::VariableDeclaration { ty: None, .. }
+        ));
+        assert!(matches!(
+            tree[1].node,
+            node::block::All::ConstantDeclaration {
+                ty: Some(common::Type::Integer),
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse("var a int = 3;"),
+            Err(error::ParserErrorType::UnexpectedTokenExpected(
+                _,
+                lexer::TokenType::Colon
+            ))
+        ));
+        assert!(matches!(
+            parse("var a = ;"),
+            Err(error::ParserErrorType::StatementEndEarly)
+        ));
+    }
+
+    #[test]
+    fn assignment_without_value() {
+        assert!(matches!(
+            parse("a = ;"),
+            Err(error::ParserErrorType::StatementEndEarly)
+        ));
+    }
+
+    #[test]
+    fn return_statement() {
+        let tree = parse("ret; ret a + 1;").unwrap();
+
+        assert!(matches!(
+            tree[0].node,
+            node::block::All::Return { value: None }
+        ));
+        assert!(matches!(
+            tree[1].node,
+            node::block::All::Return { value: Some(_) }
+        ));
+    }
+
+    #[test]
+    fn nested_blocks() {
+        let tree = parse("while a { if b { c; d; }; e = [f(g), h]; } ret;").unwrap();
+
+        assert_eq!(tree.len(), 2);
+
+        match &tree[0].node {
+            node::block::All::While { body, .. } => {
+                assert_eq!(body.len(), 2);
+                assert!(matches!(
+                    &body[0].node,
+                    node::block::All::If { then_body, .. } if then_body.len() == 2
+                ));
+            }
+            _ => panic!("Expected a while loop"),
+        }
+
+        assert!(matches!(
+            parse("a = f(b;"),
+            Err(error::ParserErrorType::ParenNotClosed)
+        ));
+        assert!(matches!(
+            parse("a = f(b];"),
+            Err(error::ParserErrorType::UnexpectedCloseSquare)
+        ));
+    }
+
+    #[test]
+    fn many_statements() {
+        let tree = parse(&"a += 1;".repeat(10_000)).unwrap();
+
+        assert_eq!(tree.len(), 10_000);
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let nested = |depth: usize| format!("{}{}", "if a { ".repeat(depth), "} ".repeat(depth));
+
+        assert!(parse(&nested(MAX_NESTING_DEPTH)).is_ok());
+        assert!(matches!(
+            parse(&nested(MAX_NESTING_DEPTH + 1)),
+            Err(error::ParserErrorType::NestingTooDeep)
+        ));
+
+        // The branches of an if statement are at the same depth, however many there are
+        let else_ifs =
+            |count: usize| format!("if a {{}}{} else {{}}", " else if a {}".repeat(count));
+        let tree = parse(&else_ifs(MAX_NESTING_DEPTH * 4)).unwrap();
+
+        let mut branches = 1;
+        let mut statement = &tree[0];
+        while let node::block::All::IfElse { else_body, .. } = &statement.node {
+            match node::block::else_if(else_body) {
+                Some(else_if) => statement = else_if,
+                None => break,
+            }
+            branches += 1;
+        }
+        assert_eq!(branches, MAX_NESTING_DEPTH * 4 + 1);
+
+        // The bodies of the branches are still one level deeper
+        assert!(matches!(
+            parse(&format!(
+                "if a {{}} else if a {{ {} }}",
+                nested(MAX_NESTING_DEPTH)
+            )),
+            Err(error::ParserErrorType::NestingTooDeep)
+        ));
+    }
+
+    #[test]
+    fn nested_types() {
+        let declaration =
+            |depth: usize| format!("var a: {}int{} = b;", "[".repeat(depth), "]".repeat(depth));
+
+        match &parse(&declaration(MAX_NESTING_DEPTH)).unwrap()[0].node {
+            node::block::All::VariableDeclaration { ty: Some(ty), .. } => {
+                let mut depth = 0;
+                let mut ty = ty;
+                while let common::Type::Array(element) = ty {
+                    depth += 1;
+                    ty = element;
+                }
+
+                assert_eq!(depth, MAX_NESTING_DEPTH);
+                assert_eq!(*ty, common::Type::Integer);
+            }
+            _ => panic!("Expected a variable declaration"),
+        }
+
+        assert!(matches!(
+            parse(&declaration(MAX_NESTING_DEPTH + 1)),
+            Err(error::ParserErrorType::NestingTooDeep)
+        ));
+        assert!(matches!(
+            parse(&declaration(100_000)),
+            Err(error::ParserErrorType::NestingTooDeep)
+        ));
+
+        // Types in declarations are limited too
+        let array = |depth: usize| format!("{}int{}", "[".repeat(depth), "]".repeat(depth));
+        for keyword in ["var", "cst"] {
+            let declaration = |depth| format!("{} a: {} = b;", keyword, array(depth));
+
+            assert!(parse(&declaration(MAX_NESTING_DEPTH)).is_ok());
+            assert!(matches!(
+                parse(&declaration(MAX_NESTING_DEPTH + 1)),
+                Err(error::ParserErrorType::NestingTooDeep)
             ));
         }
     }
+
+    #[test]
+    fn error_recovery() {
+        let mut errors = vec![];
+        let tree = gen(
+            &lexer::tokenize(
+                "a = (1; if b + { c; } d; while { e; } else f; var g = 1; ret h i;",
+                0,
+            )
+            .0,
+            0,
+            &mut errors,
+        );
+
+        assert!(matches!(
+            errors
+                .iter()
+                .map(|e| e.error_type().clone())
+                .collect::<Vec<_>>()
+                .as_slice(),
+            [
+                error::ParserErrorType::ParenNotClosed,
+                error::ParserErrorType::CannotPerformOperationOnEmpty(lexer::TokenType::Add),
+                error::ParserErrorType::EmptyExpression,
+                error::ParserErrorType::UnexpectedToken(lexer::TokenType::Else),
+                error::ParserErrorType::UnexpectedToken(lexer::TokenType::Identifier(_)),
+            ]
+        ));
+        assert!(matches!(
+            tree.iter().map(|n| &n.node).collect::<Vec<_>>().as_slice(),
+            [
+                node::block::All::Error {},
+                node::block::All::Error {},
+                node::block::All::Expression { .. },
+                node::block::All::Error {},
+                node::block::All::Error {},
+                node::block::All::VariableDeclaration { .. },
+                node::block::All::Error {},
+            ]
+        ));
+    }
 }
@@ -1,20 +1,31 @@
 use super::*;
-use cl_ln::ClLn;
+use span::Spanned;
 
-enum TypeToken {
-    Token(lexer::Token),
-    Type(common::Type),
+/// Parses a type. Array types are parsed recursively, so they can't be nested deeper than
+/// `MAX_NESTING_DEPTH`.
+pub fn gen(tokens: &[lexer::Token]) -> Result<common::Type, error::Error<error::ParserErrorType>> {
+    parse(tokens, 0)
 }
 
-pub fn gen(tokens: &[lexer::Token]) -> Result<common::Type, error::Error<error::ParserErrorType>> {
-    if tokens.len() == 0 {
-        panic!("parse_type called with no tokens");
+/// Parses a type that is nested in `depth` array types
+fn parse(
+    tokens: &[lexer::Token],
+    depth: usize,
+) -> Result<common::Type, error::Error<error::ParserErrorType>> {
+    if tokens.is_empty() {
+        return Err(empty_error(error::ParserErrorType::StatementEndEarly));
+    }
+
+    match tokens[0].token_type {
+        lexer::TokenType::Identifier(_) => return parse_struct(tokens),
+        lexer::TokenType::SquareOpen => return parse_array(tokens, depth + 1),
+        _ => {}
     }
 
     if tokens.len() != 1 {
-        return Err(error::Error::from_cl_ln(
-            error::ParserErrorType::FeatureNotImplemented("Array and struct types".to_string()),
-            &tokens[0].cl_ln(),
+        return Err(error::Error::from_span(
+            error::ParserErrorType::UnexpectedToken(tokens[1].token_type.clone()),
+            &tokens[1].span(),
         ));
     }
 
@@ -23,9 +34,76 @@ pub fn gen(tokens: &[lexer::Token]) -> Result<common::Type, error::Error<error::
         lexer::TokenType::Integer => Ok(common::Type::Integer),
         lexer::TokenType::String => Ok(common::Type::String),
         lexer::TokenType::Boolean => Ok(common::Type::Boolean),
-        _ => Err(error::Error::from_cl_ln(
+        _ => Err(error::Error::from_span(
             error::ParserErrorType::UnexpectedToken(tokens[0].token_type.clone()),
             &tokens[0],
         )),
     }
 }
+
+/// Parses an array type, the element type can be any type including another array
+///
+/// # Example
+/// ```text
+/// [int]
+/// [[geo::Point]]
+/// ```
+fn parse_array(
+    tokens: &[lexer::Token],
+    depth: usize,
+) -> Result<common::Type, error::Error<error::ParserErrorType>> {
+    check_depth(depth, &tokens[0])?;
+
+    let last = &tokens[tokens.len() - 1];
+
+    if tokens.len() == 1 || last.token_type != lexer::TokenType::SquareClose {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::SquareNotClosed,
+            &tokens[0],
+        ));
+    }
+
+    let element_tokens = &tokens[1..tokens.len() - 1];
+
+    if element_tokens.is_empty() {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::UnexpectedToken(lexer::TokenType::SquareClose),
+            last,
+        ));
+    }
+
+    Ok(common::Type::Array(Box::new(parse(element_tokens, depth)?)))
+}
+
+/// Parses the name of a struct, the namespace is resolved by the static analyzer
+///
+/// # Example
+/// ```text
+/// Point
+/// geo::Point
+/// ```
+fn parse_struct(
+    tokens: &[lexer::Token],
+) -> Result<common::Type, error::Error<error::ParserErrorType>> {
+    let mut names = vec![];
+
+    for (i, token) in tokens.iter().enumerate() {
+        match (i % 2, &token.token_type) {
+            (0, lexer::TokenType::Identifier(s)) => names.push(s.clone()),
+            (1, lexer::TokenType::DoubleColon) if i != tokens.len() - 1 => {}
+            (_, token_type) => {
+                return Err(error::Error::from_span(
+                    error::ParserErrorType::UnexpectedToken(token_type.clone()),
+                    token,
+                ));
+            }
+        }
+    }
+
+    let identifier = names.pop().unwrap();
+
+    Ok(common::Type::Struct {
+        namespace: names,
+        identifier,
+    })
+}
@@ -0,0 +1,105 @@
+use super::*;
+
+/// Returns if the token type is `=` or any of the compound assignment operators
+pub fn is_assign(token_type: &lexer::TokenType) -> bool {
+    use lexer::TokenType::*;
+
+    matches!(
+        token_type,
+        Assign | AddAssign | SubAssign | MulAssign | DivAssign | ModAssign | PowAssign
+    )
+}
+
+/// Parses a variable assignment. Compound assignments keep their operator, i.e. `a += 1` is parsed
+/// as an assignment to `a` with the operator `Add` and the value `1`.
+///
+/// Assumes that the tokens contain an assignment operator, everything before it is parsed as the
+/// target of the assignment
+///
+/// # Example
+/// ```text
+/// a = 3
+/// a **= 2
+/// a.b.c = 4
+/// a[0].b = 5
+/// ```
+pub fn gen(
+    tokens: &[lexer::Token],
+) -> Result<node::BlockNode<node::expression::All>, error::Error<error::ParserErrorType>> {
+    let op = tokens
+        .iter()
+        .position(|t| is_assign(&t.token_type))
+        .unwrap();
+
+    if op == 0 {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::MissingIdentifier,
+            &tokens[0],
+        ));
+    }
+
+    let target = expression::gen(&tokens[..op])?;
+
+    // Only variables, their fields and their elements can be assigned to
+    if !is_place(&target) {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::InvalidAssignmentTarget,
+            &target,
+        ));
+    }
+
+    let value_tokens = &tokens[op + 1..];
+
+    if value_tokens.is_empty() {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::StatementEndEarly,
+            &tokens[op],
+        ));
+    }
+
+    let value = expression::gen(value_tokens)?;
+
+    let operator = match tokens[op].token_type {
+        lexer::TokenType::Assign => None,
+        lexer::TokenType::AddAssign => Some(lexer::TokenType::Add),
+        lexer::TokenType::SubAssign => Some(lexer::TokenType::Sub),
+        lexer::TokenType::MulAssign => Some(lexer::TokenType::Mul),
+        lexer::TokenType::DivAssign => Some(lexer::TokenType::Div),
+        lexer::TokenType::ModAssign => Some(lexer::TokenType::Mod),
+        lexer::TokenType::PowAssign => Some(lexer::TokenType::Pow),
+        _ => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::UnexpectedTokenExpected(
+                    tokens[op].token_type.clone(),
+                    lexer::TokenType::Assign,
+                ),
+                &tokens[op],
+            ));
+        }
+    };
+
+    Ok(Node::from_span(
+        node::block::All::VariableAssignment {
+            target,
+            operator,
+            value,
+        },
+        &span::combine(tokens),
+    ))
+}
+
+/// Returns if the expression is a variable, or a property or an element of a variable
+fn is_place(expression: &Node<node::expression::All>) -> bool {
+    match &expression.node {
+        node::expression::All::SingleDataUnit {
+            value: node::expression::SingleDataUnit::Identifier { .. },
+        } => true,
+        node::expression::All::SingleDataUnit {
+            value: node::expression::SingleDataUnit::PropertyAccess { object, .. },
+        } => is_place(object),
+        node::expression::All::SingleDataUnit {
+            value: node::expression::SingleDataUnit::ArrayAccess { array, .. },
+        } => is_place(array),
+        _ => false,
+    }
+}
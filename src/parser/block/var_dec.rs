@@ -1,65 +1,109 @@
 use super::*;
 
+/// Parses a variable or a constant declaration, depending on if the first token is `var` or `cst`
+///
+/// # Example
+/// ```text
+/// var a: int = 3
+/// cst b: flt = 4.0
+/// var c = a
+/// ```
 pub fn gen(
     tokens: &[lexer::Token],
-) -> Result<Node<node::block::All<Node<node::expression::All>>>, error::Error<error::ParserErrorType>>
-{
-    if tokens.len() == 0 {
-        panic!("var_dec called with no tokens");
-    }
+) -> Result<node::BlockNode<node::expression::All>, error::Error<error::ParserErrorType>> {
+    let (identifier, ty, value) = parts(tokens)?;
 
-    if tokens.len() < 6 {
-        return Err(error::Error::from_cl_ln(
-            error::ParserErrorType::StatementEndEarly,
-            &tokens[0],
-        ));
+    let node = if tokens[0].token_type == lexer::TokenType::Const {
+        node::block::All::ConstantDeclaration {
+            identifier,
+            ty,
+            value,
+        }
+    } else {
+        node::block::All::VariableDeclaration {
+            identifier,
+            ty,
+            value,
+        }
+    };
+
+    Ok(Node::from_span(node, &span::combine(tokens)))
+}
+
+/// Parses the identifier, type and value of a declaration. The first token, `var` or `cst`, is
+/// ignored. The type is optional, if it's left out it will be inferred by the static analyzer.
+///
+/// # Example
+/// ```text
+/// var a: int = 3
+/// var b = 3
+/// ```
+pub fn parts(
+    tokens: &[lexer::Token],
+) -> Result<
+    (String, Option<common::Type>, Node<node::expression::All>),
+    error::Error<error::ParserErrorType>,
+> {
+    if tokens.is_empty() {
+        return Err(empty_error(error::ParserErrorType::StatementEndEarly));
     }
 
-    let variable_name = match &tokens[1].token_type {
-        lexer::TokenType::Identifier(s) => s,
+    let variable_name = match tokens.get(1).map(|t| &t.token_type) {
+        Some(lexer::TokenType::Identifier(s)) => s,
         _ => {
-            return Err(error::Error::from_cl_ln(
+            return Err(error::Error::from_span(
                 error::ParserErrorType::MissingIdentifier,
                 &tokens[0],
             ));
         }
     };
 
-    if tokens[2].token_type != lexer::TokenType::Colon {
-        return Err(error::Error::from_cl_ln(
-            error::ParserErrorType::UnexpectedTokenExpected(
-                tokens[2].token_type.clone(),
-                lexer::TokenType::Colon,
-            ),
-            &tokens[2],
-        ));
-    }
-
-    for i in 3..tokens.len() {
-        if tokens[i].token_type == lexer::TokenType::Assign {
-            let ty = &tokens[3..i];
-            let rest_tokens = &tokens[i + 1..];
+    let assign = match tokens
+        .iter()
+        .position(|t| t.token_type == lexer::TokenType::Assign)
+    {
+        Some(i) => i,
+        None => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::StatementEndEarly,
+                &tokens[tokens.len() - 1],
+            ));
+        }
+    };
 
-            if ty.len() == 0 {
-                return Err(error::Error::from_cl_ln(
+    let ty = match &tokens[2..assign] {
+        [] => None,
+        [colon, ty @ ..] if colon.token_type == lexer::TokenType::Colon => {
+            if ty.is_empty() {
+                return Err(error::Error::from_span(
                     error::ParserErrorType::UnexpectedToken(lexer::TokenType::Assign),
-                    &tokens[3],
+                    &tokens[assign],
                 ));
             }
 
-            let ty = r#type::gen(ty)?;
-            let exp = expression::gen(rest_tokens)?;
-
-            return Ok(Node::from_cl_ln(
-                node::block::All::VariableDeclaration {
-                    identifier: variable_name.to_string(),
-                    ty,
-                    value: exp,
-                },
-                &cl_ln::combine(tokens),
+            Some(r#type::gen(ty)?)
+        }
+        [t, ..] => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::UnexpectedTokenExpected(
+                    t.token_type.clone(),
+                    lexer::TokenType::Colon,
+                ),
+                t,
             ));
         }
+    };
+
+    let rest_tokens = &tokens[assign + 1..];
+
+    if rest_tokens.is_empty() {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::StatementEndEarly,
+            &tokens[assign],
+        ));
     }
 
-    todo!()
+    let exp = expression::gen(rest_tokens)?;
+
+    Ok((variable_name.to_string(), ty, exp))
 }
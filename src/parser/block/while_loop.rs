@@ -0,0 +1,56 @@
+use super::*;
+
+/// Parses a while loop
+///
+/// Assumes that the first token is `while`. Returns the node and how many tokens it consumed.
+///
+/// # Example
+/// ```text
+/// while i < 10 {
+///     i += 1;
+/// }
+/// ```
+pub fn gen(
+    tokens: &[lexer::Token],
+    depth: usize,
+    errors: &mut Vec<error::Error<error::ParserErrorType>>,
+) -> Result<(node::BlockNode<node::expression::All>, usize), error::Error<error::ParserErrorType>> {
+    check_depth(depth + 1, &tokens[0])?;
+
+    let body_start = match tokens
+        .iter()
+        .position(|t| t.token_type == lexer::TokenType::CurlyOpen)
+    {
+        Some(i) => i,
+        None => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::StatementEndEarly,
+                &tokens[0],
+            ));
+        }
+    };
+
+    let cond_tokens = &tokens[1..body_start];
+
+    if cond_tokens.is_empty() {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::EmptyExpression,
+            &span::combine(&tokens[..=body_start]),
+        ));
+    }
+
+    let cond = expression::gen(cond_tokens)?;
+
+    let body_end = body_start + curly_end(&tokens[body_start..])?;
+    let body = super::gen(&tokens[body_start + 1..body_end], depth + 1, errors);
+
+    let consumed = body_end + 1;
+
+    Ok((
+        Node::from_span(
+            node::block::All::While { cond, body },
+            &span::combine(&tokens[..consumed]),
+        ),
+        consumed,
+    ))
+}
@@ -1,16 +1,14 @@
 use super::*;
 
-mod exp_token;
 mod operations;
 mod single_data_unit;
-use exp_token::*;
 
 use node::{expression, Node};
 
 /// The expression parser. An expression is anything that can be evaluated to a single value.
 ///
 /// # Examples:
-/// ```
+/// ```text
 /// 3 + 4;
 /// 6 + 8 * 9 - 10;
 /// (my_variable[6 * -(3 + 4)].property + 3) * 4;
@@ -18,125 +16,253 @@ use node::{expression, Node};
 pub fn gen(
     tokens: &[lexer::Token],
 ) -> Result<Node<expression::All>, error::Error<error::ParserErrorType>> {
-    if tokens.len() == 0 {
-        panic!("Cannot parse empty expression");
+    if tokens.is_empty() {
+        return Err(empty_error(error::ParserErrorType::EmptyExpression));
     }
 
-    if tokens[0]
-        .token_type
-        .shallow_eq(&lexer::TokenType::CompilerSetCustomCodePreDefined(
-            "".to_string(),
-        ))
-    {
-        if tokens.len() != 2 {
-            match &tokens[1].token_type {
-                lexer::TokenType::StringLiteral(s) => {
-                    return Ok(Node::from_cl_ln(
-                        expression::All::CompilerCustomCodePreDefined { value: s.clone() },
-                        &tokens[0],
-                    ));
-                }
-                _ => {
-                    return Err(error::Error::from_cl_ln(
-                        error::ParserErrorType::CompilerCustomCodePreDefined,
-                        &tokens[1],
-                    ));
-                }
-            }
+    let mut parser = Parser::new(tokens);
+    let expression = operations::gen(&mut parser, 0)?;
+
+    match parser.peek() {
+        Some(token) => Err(unexpected(token)),
+        None => Ok(expression),
+    }
+}
+
+/// A cursor over the tokens of an expression. The parser only ever looks at the next token, so
+/// every token is visited once and an expression is parsed in linear time.
+struct Parser<'a> {
+    tokens: &'a [lexer::Token],
+    position: usize,
+    /// How many values that are being parsed the current value is nested in, e.g. through
+    /// parentheses or the right side of `**`
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: &'a [lexer::Token]) -> Self {
+        Parser {
+            tokens,
+            position: 0,
+            depth: 0,
         }
+    }
 
-        return Err(error::Error::from_cl_ln(
-            error::ParserErrorType::CompilerCustomCodePreDefined,
-            &tokens[0],
-        ));
+    fn peek(&self) -> Option<&'a lexer::Token> {
+        self.tokens.get(self.position)
     }
 
-    // Since `exp::gen` expects that all parentheses are already calculated, this function will
-    // first parse all parentheses and then send the tokens to `exp::gen`
-
-    // How do we se the difference between a function call and a parenthesized expression?
-    // - If the first token is an identifier, it's a function call
-    // - Otherwise it's a parenthesized expression
-
-    // This means that it's not possible to call a function from anything else than an identifier
-    // Example:
-    // (3 + 4)() // This is not possible
-    // my_function() // This is possible
-    // (my_function)() // This is not possible
-    // (my_function()) // This is possible
-
-    // Pretty ugly code that will parse all parentheses
-    let mut ret_tokens: Vec<ExpressionToken> = vec![];
-    let mut peek_tokens = tokens.iter().enumerate().peekable();
-    let mut p_count = 0;
-    let mut p_start: Option<usize> = None;
-    while let Some((i, t)) = peek_tokens.next() {
-        use lexer::TokenType::*;
-
-        if p_start.is_some() {
-            if t.token_type == ParenOpen {
-                p_count += 1;
-            } else if t.token_type == ParenClose {
-                p_count -= 1;
-
-                if p_count < 0 {
-                    return Err(error::Error::from_cl_ln(
-                        error::ParserErrorType::UnexpectedCloseParen,
-                        t,
-                    ));
-                }
-
-                if p_count == 0 {
-                    let p_end = i;
-
-                    let p_tokens = &tokens[p_start.unwrap() + 1..p_end];
-
-                    if p_tokens.len() == 0 {
-                        return Err(error::Error::from_cl_ln(
-                            error::ParserErrorType::EmptyExpression,
-                            &cl_ln::combine(&tokens[p_start.unwrap()..=p_end]),
-                        ));
-                    }
-
-                    let p_tokens = gen(p_tokens)?;
-
-                    p_start = None;
-
-                    ret_tokens.push(ExpressionToken::Expression(p_tokens));
-                }
-            }
-            continue;
+    fn next(&mut self) -> Option<&'a lexer::Token> {
+        let token = self.peek()?;
+        self.position += 1;
+
+        Some(token)
+    }
+
+    /// Consumes the next token if it's of the given type
+    fn eat(&mut self, token_type: &lexer::TokenType) -> Option<&'a lexer::Token> {
+        match self.peek() {
+            Some(token) if &token.token_type == token_type => self.next(),
+            _ => None,
         }
+    }
 
-        let can_begin_match = match &t.token_type {
-            Identifier(_) => false,
-            _ => true,
-        };
+    /// Returns if the next token can be the start of a value
+    fn at_value(&self) -> bool {
+        self.peek()
+            .is_some_and(|token| single_data_unit::can_begin(&token.token_type))
+    }
+}
+
+/// The error for a token that can't be where it is, closing brackets have errors of their own
+fn unexpected(token: &lexer::Token) -> error::Error<error::ParserErrorType> {
+    let error_type = match &token.token_type {
+        lexer::TokenType::ParenClose => error::ParserErrorType::UnexpectedCloseParen,
+        lexer::TokenType::CurlyClose => error::ParserErrorType::UnexpectedCloseCurly,
+        lexer::TokenType::SquareClose => error::ParserErrorType::UnexpectedCloseSquare,
+        token_type => error::ParserErrorType::UnexpectedToken(token_type.clone()),
+    };
+
+    error::Error::from_span(error_type, token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(code: &str) -> Result<Node<expression::All>, error::ParserErrorType> {
+        gen(&lexer::tokenize(code, 0).0).map_err(|e| e.error_type().clone())
+    }
+
+    /// Writes the expression with parentheses around every operation
+    fn show(node: &Node<expression::All>) -> String {
+        use expression::{All, Arithmetic as A, BooleanLogic as B, Cmp as C, SingleDataUnit as S};
 
-        if i == 0 && t.token_type == ParenOpen {
-            p_count += 1;
-            p_start = Some(i);
-            continue;
-        } else if can_begin_match {
-            if let Some((_, nt)) = peek_tokens.peek() {
-                if nt.token_type == ParenOpen {
-                    p_count += 1;
-                    p_start = Some(i + 1);
-                    // Hack to skip the next token
-                    peek_tokens.nth(0);
-                }
+        let (op, left, right) = match &node.node {
+            All::Arithmetic {
+                value: A::Neg { value },
+            } => return format!("(-{})", show(value)),
+            All::BooleanLogic {
+                value: B::Not { value },
+            } => return format!("(!{})", show(value)),
+            All::Arithmetic { value } => match value {
+                A::Add { left, right } => ("+", left, right),
+                A::Sub { left, right } => ("-", left, right),
+                A::Mul { left, right } => ("*", left, right),
+                A::Div { left, right } => ("/", left, right),
+                A::Mod { left, right } => ("%", left, right),
+                A::Pow { left, right } => ("**", left, right),
+                A::Neg { .. } => unreachable!(),
+            },
+            All::BooleanLogic { value } => match value {
+                B::Or { left, right } => ("||", left, right),
+                B::And { left, right } => ("&&", left, right),
+                B::Not { .. } => unreachable!(),
+            },
+            All::Cmp { value } => match value {
+                C::Equal { left, right } => ("==", left, right),
+                C::NotEqual { left, right } => ("!=", left, right),
+                C::LessThan { left, right } => ("<", left, right),
+                C::LessThanOrEqual { left, right } => ("<=", left, right),
+                C::GreaterThan { left, right } => (">", left, right),
+                C::GreaterThanOrEqual { left, right } => (">=", left, right),
+            },
+            All::SingleDataUnit {
+                value: S::Identifier { identifier, .. },
+            } => return identifier.clone(),
+            All::SingleDataUnit {
+                value:
+                    S::FunctionCall {
+                        function,
+                        arguments,
+                    },
+            } => {
+                let arguments = arguments
+                    .iter()
+                    .map(|(is_out, arg)| {
+                        format!("{}{}", if *is_out { "out " } else { "" }, show(arg))
+                    })
+                    .collect::<Vec<_>>();
+                return format!("{}({})", show(function), arguments.join(", "));
             }
+            All::SingleDataUnit {
+                value: S::ArrayAccess { array, index },
+            } => return format!("{}[{}]", show(array), show(index)),
+            All::SingleDataUnit {
+                value: S::PropertyAccess { object, property },
+            } => return format!("{}.{}", show(object), property),
+            _ => return "_".to_string(),
+        };
+
+        format!("({} {} {})", show(left), op, show(right))
+    }
+
+    #[test]
+    fn precedence() {
+        for (code, expected) in [
+            ("a + b % c", "(a + (b % c))"),
+            ("a - b - c", "((a - b) - c)"),
+            ("a * b + c * d", "((a * b) + (c * d))"),
+            ("a || b && c", "(a || (b && c))"),
+            ("a && b || c", "((a && b) || c)"),
+            ("a < b == c > d", "((a < b) == (c > d))"),
+            ("a + b < c || !d", "(((a + b) < c) || (!d))"),
+            ("a ** b ** c", "(a ** (b ** c))"),
+            ("-a ** b", "(-(a ** b))"),
+            ("a ** -b * c", "((a ** (-b)) * c)"),
+            ("-a * b", "((-a) * b)"),
+            ("!a == b", "((!a) == b)"),
+            ("- -a", "(-(-a))"),
+            ("(a + b) * c", "((a + b) * c)"),
+            ("-f(a, out b)[c + e].d", "(-f(a, out b)[(c + e)].d)"),
+        ] {
+            assert_eq!(show(&parse(code).unwrap()), expected, "{}", code);
         }
+    }
+
+    #[test]
+    fn long_expression() {
+        let code = vec!["a"; 10_000].join(" + ");
+        let mut node = &parse(&code).unwrap();
+        let mut depth = 0;
+
+        while let expression::All::Arithmetic {
+            value: expression::Arithmetic::Add { left, .. },
+        } = &node.node
+        {
+            node = left;
+            depth += 1;
+        }
+
+        assert_eq!(depth, 9_999);
+
+        // The analyzer and the compiler evaluate every operand once and walk the chain in a loop,
+        // so they handle the expression as well
+        let code = format!("fnc f(a: int) -> int {{ ret {}; }}", code);
+        let mut source_map = span::SourceMap::new();
+        let file_id = source_map.add_file("main.zy", &code);
 
-        ret_tokens.push(ExpressionToken::Token(t.clone()));
+        let (tree, errors) = parser::gen(&lexer::tokenize(&code, file_id).0);
+        assert!(errors.is_empty());
+        let typed_tree = static_analyzer::evaluate_freestanding(&tree).unwrap();
+
+        let output = compiler::javascript::compile(&typed_tree, &source_map);
+        let sum = format!("{}v1a.value", "(".repeat(9_999));
+        assert!(output.contains(&format!("return {}) + (v1a.value)) + (", sum)));
+        assert!(output.contains(") + (v1a.value);}"));
     }
 
-    if p_count > 0 {
-        return Err(error::Error::from_cl_ln(
-            error::ParserErrorType::UnclosedExpression,
-            &tokens[p_start.unwrap()],
+    #[test]
+    fn nesting_depth() {
+        let nested = |depth: usize| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert!(parse(&nested(MAX_NESTING_DEPTH - 1)).is_ok());
+        assert!(matches!(
+            parse(&nested(MAX_NESTING_DEPTH)),
+            Err(error::ParserErrorType::NestingTooDeep)
+        ));
+        assert!(matches!(
+            parse(&"-[".repeat(100_000)),
+            Err(error::ParserErrorType::NestingTooDeep)
         ));
     }
 
-    operations::gen(&ret_tokens)
+    #[test]
+    fn errors() {
+        use error::ParserErrorType as ET;
+        use lexer::TokenType as TT;
+
+        assert!(matches!(
+            parse("a +"),
+            Err(ET::CannotPerformOperationOnEmpty(TT::Add))
+        ));
+        assert!(matches!(
+            parse("* a"),
+            Err(ET::CannotPerformOperationOnEmpty(TT::Mul))
+        ));
+        assert!(matches!(
+            parse("a + * b"),
+            Err(ET::CannotPerformOperationOnEmpty(TT::Add))
+        ));
+        assert!(matches!(
+            parse("-"),
+            Err(ET::CannotPerformOperationOnEmpty(TT::Sub))
+        ));
+        assert!(matches!(parse("(a + b"), Err(ET::UnclosedExpression)));
+        assert!(matches!(parse("()"), Err(ET::EmptyExpression)));
+        assert!(matches!(parse("a)"), Err(ET::UnexpectedCloseParen)));
+        assert!(matches!(parse("f(a"), Err(ET::ParenNotClosed)));
+        assert!(matches!(parse("f(a,, b)"), Err(ET::EmptyExpression)));
+        assert!(matches!(parse("a[1"), Err(ET::SquareNotClosed)));
+        assert!(matches!(parse("a[1)"), Err(ET::UnexpectedCloseParen)));
+        assert!(matches!(parse("a."), Err(ET::NoPropertyOnAccess)));
+        assert!(matches!(
+            parse("a b"),
+            Err(ET::UnexpectedToken(TT::Identifier(_)))
+        ));
+        assert!(matches!(
+            parse("a::"),
+            Err(ET::UnexpectedToken(TT::DoubleColon))
+        ));
+    }
 }
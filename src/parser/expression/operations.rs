@@ -1,154 +1,162 @@
 use super::*;
+use crate::span::Spanned;
 use parser::node::expression;
 
-const ORDER_OF_OPERATIONS: &'static [&'static [lexer::TokenType]] = {
-    use lexer::TokenType::*;
+/// The binding power of `-` and `!`. It's higher than the binding power of every binary operator
+/// except `**`, so `-a * b` is `(-a) * b` while `-a ** b` is `-(a ** b)`.
+const PREFIX_BINDING_POWER: u8 = 13;
 
-    &[
-        &[Pow],
-        &[Mul, Div],
-        &[Add, Sub],
-        &[Mod],
-        &[
-            Equal,
-            NotEqual,
-            LessThan,
-            LessThanOrEqual,
-            GreaterThan,
-            GreaterThanOrEqual,
-        ],
-        &[Or, And],
-    ]
-};
-
-fn is_op(token_type: &lexer::TokenType) -> bool {
+/// Returns the binding power of a binary operator to its left and its right, or `None` if the token
+/// isn't a binary operator. The operator with the higher binding power is evaluated first. A right
+/// binding power lower than the left one makes the operator right-associative, e.g. `**`.
+fn binding_power(token_type: &lexer::TokenType) -> Option<(u8, u8)> {
     use lexer::TokenType::*;
 
     match token_type {
-        Add | Sub | Mul | Div | Mod | Or | And | Equal | NotEqual | LessThan | LessThanOrEqual
-        | GreaterThan | GreaterThanOrEqual | Not => true,
-        _ => false,
+        Or => Some((1, 2)),
+        And => Some((3, 4)),
+        Equal | NotEqual => Some((5, 6)),
+        LessThan | LessThanOrEqual | GreaterThan | GreaterThanOrEqual => Some((7, 8)),
+        Add | Sub => Some((9, 10)),
+        Mul | Div | Mod => Some((11, 12)),
+        Pow => Some((16, 15)),
+        _ => None,
     }
 }
 
-/// Parses comparison operators with parentheses already calculated
+/// Parses operators with precedence climbing. Only operators that bind at least as hard as
+/// `min_binding_power` are parsed, the rest are left for the caller.
 ///
 /// # Example
+/// ```text
 /// 3 + 4 * 5 - (6 + 7)
-///
-/// - (6 + 7) is presumed to be pre-calculated
-/// - Go through all other operators in order of operations and send the values in between to
-/// single_data_unit parsing
+/// ```
+/// - `3` is parsed as a single data unit
+/// - `+` binds harder than 0, so `4 * 5` is parsed with the right binding power of `+`
+/// - `-` doesn't bind harder than the right binding power of `+`, so it's left for the loop that
+///   parsed `+`, which gives `(3 + (4 * 5)) - (6 + 7)`
 pub(super) fn gen(
-    tokens: &[ExpressionToken],
+    parser: &mut Parser,
+    min_binding_power: u8,
 ) -> Result<Node<expression::All>, error::Error<error::ParserErrorType>> {
-    for order_of_operations in ORDER_OF_OPERATIONS.iter().rev() {
-        let itr = tokens.iter();
-
-        let mut curly_count = 0;
-        let mut square_count = 0;
-        let mut paren_count = 0;
-
-        for (i, token) in itr.enumerate() {
-            if let ExpressionToken::Token(token) = token {
-                if token.token_type == lexer::TokenType::Sub {
-                    if i == 0 {
-                        continue;
-                    }
-
-                    if let ExpressionToken::Token(t) = &tokens[i - 1] {
-                        if is_op(&t.token_type) {
-                            continue;
-                        }
-                    }
-                }
+    parser.depth += 1;
 
-                match &token.token_type {
-                    lexer::TokenType::ParenOpen => paren_count += 1,
-                    lexer::TokenType::ParenClose => paren_count -= 1,
-                    lexer::TokenType::CurlyOpen => curly_count += 1,
-                    lexer::TokenType::CurlyClose => curly_count -= 1,
-                    lexer::TokenType::SquareOpen => square_count += 1,
-                    lexer::TokenType::SquareClose => square_count -= 1,
-                    _ => {}
-                }
+    if let Some(token) = parser.peek() {
+        check_depth(parser.depth, token)?;
+    }
 
-                if curly_count < 0 || square_count < 0 || paren_count < 0 {
-                    return Err(error::Error::from_cl_ln(
-                        {
-                            if curly_count < 0 {
-                                error::ParserErrorType::UnexpectedCloseCurly
-                            } else if square_count < 0 {
-                                error::ParserErrorType::UnexpectedCloseSquare
-                            } else {
-                                error::ParserErrorType::UnexpectedCloseParen
-                            }
-                        },
-                        token,
-                    ));
-                }
+    let mut left = prefix(parser)?;
 
-                if curly_count != 0 || square_count != 0 || paren_count != 0 {
-                    continue;
-                }
+    while let Some(token) = parser.peek() {
+        let (left_binding_power, right_binding_power) = match binding_power(&token.token_type) {
+            Some(binding_power) => binding_power,
+            None => break,
+        };
 
-                if order_of_operations.contains(&&token.token_type) {
-                    let left = &tokens[..i];
-                    let right = &tokens[i + 1..];
-
-                    macro_rules! matcher {
-                        ($s:ident::$x:ident) => {{
-                            if left.len() == 0 || right.len() == 0 {
-                                return Err(error::Error::from_cl_ln(
-                                    error::ParserErrorType::CannotPerformOperationOnEmpty(
-                                        lexer::TokenType::$x,
-                                    ),
-                                    token,
-                                ));
-                            }
-
-                            let left = gen(left)?;
-                            let right = gen(right)?;
-
-                            let cl_ln = cl_ln::combine(&tokens);
-
-                            Node::from_cl_ln(
-                                expression::All::$s {
-                                    value: expression::$s::$x {
-                                        left: Box::new(left),
-                                        right: Box::new(right),
-                                    },
-                                },
-                                &cl_ln,
-                            )
-                        }};
-                        ($x:ident) => {
-                            lexer::TokenType::$x
-                        };
-                    }
-
-                    return Ok(match token.token_type {
-                        matcher!(Add) => matcher!(Arithmetic::Add),
-                        matcher!(Pow) => matcher!(Arithmetic::Pow),
-                        matcher!(Sub) => matcher!(Arithmetic::Sub),
-                        matcher!(Mul) => matcher!(Arithmetic::Mul),
-                        matcher!(Div) => matcher!(Arithmetic::Div),
-                        matcher!(Mod) => matcher!(Arithmetic::Mod),
-                        matcher!(Or) => matcher!(BooleanLogic::Or),
-                        matcher!(And) => matcher!(BooleanLogic::And),
-                        matcher!(Equal) => matcher!(Cmp::Equal),
-                        matcher!(NotEqual) => matcher!(Cmp::NotEqual),
-                        matcher!(LessThan) => matcher!(Cmp::LessThan),
-                        matcher!(LessThanOrEqual) => matcher!(Cmp::LessThanOrEqual),
-                        matcher!(GreaterThan) => matcher!(Cmp::GreaterThan),
-                        matcher!(GreaterThanOrEqual) => matcher!(Cmp::GreaterThanOrEqual),
-
-                        _ => unreachable!(),
-                    });
-                }
+        if left_binding_power < min_binding_power {
+            break;
+        }
+
+        parser.next();
+
+        if !parser.at_value() {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::CannotPerformOperationOnEmpty(token.token_type.clone()),
+                token,
+            ));
+        }
+
+        let right = gen(parser, right_binding_power)?;
+        left = binary(token, left, right);
+    }
+
+    parser.depth -= 1;
+
+    Ok(left)
+}
+
+/// Parses a value together with the unary operators in front of it
+fn prefix(
+    parser: &mut Parser,
+) -> Result<Node<expression::All>, error::Error<error::ParserErrorType>> {
+    let token = match parser.peek() {
+        Some(token) => token,
+        None => return single_data_unit::gen(parser),
+    };
+
+    match &token.token_type {
+        lexer::TokenType::Sub | lexer::TokenType::Not => {
+            parser.next();
+
+            if !parser.at_value() {
+                return Err(error::Error::from_span(
+                    error::ParserErrorType::CannotPerformOperationOnEmpty(token.token_type.clone()),
+                    token,
+                ));
             }
+
+            let value = Box::new(gen(parser, PREFIX_BINDING_POWER)?);
+            let span = token.combine(value.span());
+
+            let value = if token.token_type == lexer::TokenType::Sub {
+                expression::All::Arithmetic {
+                    value: expression::Arithmetic::Neg { value },
+                }
+            } else {
+                expression::All::BooleanLogic {
+                    value: expression::BooleanLogic::Not { value },
+                }
+            };
+
+            Ok(Node::from_span(value, &span))
         }
+        token_type if binding_power(token_type).is_some() => Err(error::Error::from_span(
+            error::ParserErrorType::CannotPerformOperationOnEmpty(token_type.clone()),
+            token,
+        )),
+        _ => single_data_unit::gen(parser),
+    }
+}
+
+/// Combines two values with a binary operator
+fn binary(
+    operator: &lexer::Token,
+    left: Node<expression::All>,
+    right: Node<expression::All>,
+) -> Node<expression::All> {
+    let span = left.combine(right.span());
+    let left = Box::new(left);
+    let right = Box::new(right);
+
+    macro_rules! matcher {
+        ($s:ident::$x:ident) => {
+            expression::All::$s {
+                value: expression::$s::$x { left, right },
+            }
+        };
+        ($x:ident) => {
+            lexer::TokenType::$x
+        };
     }
 
-    single_data_unit::all(tokens)
+    let value = match operator.token_type {
+        matcher!(Add) => matcher!(Arithmetic::Add),
+        matcher!(Pow) => matcher!(Arithmetic::Pow),
+        matcher!(Sub) => matcher!(Arithmetic::Sub),
+        matcher!(Mul) => matcher!(Arithmetic::Mul),
+        matcher!(Div) => matcher!(Arithmetic::Div),
+        matcher!(Mod) => matcher!(Arithmetic::Mod),
+        matcher!(Or) => matcher!(BooleanLogic::Or),
+        matcher!(And) => matcher!(BooleanLogic::And),
+        matcher!(Equal) => matcher!(Cmp::Equal),
+        matcher!(NotEqual) => matcher!(Cmp::NotEqual),
+        matcher!(LessThan) => matcher!(Cmp::LessThan),
+        matcher!(LessThanOrEqual) => matcher!(Cmp::LessThanOrEqual),
+        matcher!(GreaterThan) => matcher!(Cmp::GreaterThan),
+        matcher!(GreaterThanOrEqual) => matcher!(Cmp::GreaterThanOrEqual),
+
+        _ => unreachable!(),
+    };
+
+    Node::from_span(value, &span)
 }
@@ -1,6 +1,24 @@
 use super::*;
-use crate::cl_ln::ClLn;
+use crate::span::Spanned;
 use parser::node::expression;
+use std::collections::HashMap;
+
+/// Returns if a token can be the first token of a value, including the unary operators
+pub(super) fn can_begin(token_type: &lexer::TokenType) -> bool {
+    use lexer::TokenType::*;
+
+    matches!(
+        token_type,
+        Sub | Not
+            | ParenOpen
+            | SquareOpen
+            | Identifier(_)
+            | IntegerLiteral(_)
+            | FloatLiteral(_)
+            | StringLiteral(_)
+            | BooleanLiteral(_)
+    )
+}
 
 // Parses all single data units. This is the smallest unit of an expression. For example literals,
 // function calls, property access, etc.
@@ -12,528 +30,473 @@ use parser::node::expression;
 // "Hello world"
 // my_function()
 // my_variable
-// std::print(*already evaluated*)[0].property
-pub(super) fn all(
-    tokens: &[ExpressionToken],
+// (3 + 4)
+// std::print(3)[0].property
+pub(super) fn gen(
+    parser: &mut Parser,
 ) -> Result<Node<expression::All>, error::Error<error::ParserErrorType>> {
-    if tokens.len() == 0 {
-        // Should never get here
-        panic!("Cannot parse empty expression");
+    let token = match parser.next() {
+        Some(token) => token,
+        None => return Err(empty_error(error::ParserErrorType::EmptyExpression)),
+    };
+
+    macro_rules! literal {
+        ($scope:ident::$type:ident, $v:expr) => {
+            Node::from_span(
+                expression::All::SingleDataUnit {
+                    value: expression::SingleDataUnit::Literal {
+                        literal: expression::$scope::$type { value: $v },
+                    },
+                },
+                token,
+            )
+        };
     }
 
-    if tokens.len() >= 1 {
-        if let ExpressionToken::Token(t) = &tokens[0] {
-            match t.token_type {
-                lexer::TokenType::Not => {
-                    let value = &tokens[1..];
-                    let value = all(value)?;
+    let mut expression = match &token.token_type {
+        lexer::TokenType::FloatLiteral(f) => literal!(Literal::Float, *f),
+        lexer::TokenType::IntegerLiteral(i) => literal!(Literal::Integer, *i),
+        lexer::TokenType::StringLiteral(s) => literal!(Literal::String, s.to_string()),
+        lexer::TokenType::BooleanLiteral(b) => literal!(Literal::Boolean, *b),
+        lexer::TokenType::ParenOpen => parse_parentheses(parser, token)?,
+        lexer::TokenType::SquareOpen => parse_array_init(parser, token)?,
+        lexer::TokenType::Identifier(_) => parse_identifier(parser, token)?,
+        _ => return Err(unexpected(token)),
+    };
 
-                    let cl_ln = cl_ln::combine(&tokens);
+    while let Some(token) = parser.peek() {
+        expression = match &token.token_type {
+            lexer::TokenType::Dot => parse_property_access(parser, expression)?,
+            lexer::TokenType::SquareOpen => parse_array_access(parser, expression)?,
+            lexer::TokenType::ParenOpen => parse_function_call(parser, expression)?,
+            lexer::TokenType::CurlyOpen => parse_struct_init(parser, expression)?,
+            _ => break,
+        };
+    }
 
-                    return Ok(Node::from_cl_ln(
-                        expression::All::BooleanLogic {
-                            value: expression::BooleanLogic::Not {
-                                value: Box::new(value),
-                            },
-                        },
-                        &cl_ln,
-                    ));
-                }
-                lexer::TokenType::Sub => {
-                    let value = &tokens[1..];
-                    let value = all(value)?;
+    Ok(expression)
+}
 
-                    let cl_ln = cl_ln::combine(&tokens);
+/// Parses the values of a list separated by commas until the closing token, a trailing comma is
+/// allowed. Expects that the opening token is already consumed, returns the values together with
+/// the closing token.
+///
+/// # Example
+/// ```text
+/// 1, 2, 3]
+/// x: 1, y: 2, }
+/// ```
+fn list<'a, T>(
+    parser: &mut Parser<'a>,
+    open: &lexer::Token,
+    close: lexer::TokenType,
+    not_closed: error::ParserErrorType,
+    mut value: impl FnMut(&mut Parser<'a>) -> Result<T, error::Error<error::ParserErrorType>>,
+) -> Result<(Vec<T>, &'a lexer::Token), error::Error<error::ParserErrorType>> {
+    let mut values = vec![];
+
+    loop {
+        match parser.peek() {
+            Some(token) if token.token_type == close => break,
+            // Only the last value can be empty, e.g. when there's a trailing comma
+            Some(token) if token.token_type == lexer::TokenType::Comma => {
+                return Err(error::Error::from_span(
+                    error::ParserErrorType::EmptyExpression,
+                    token,
+                ));
+            }
+            Some(_) => {}
+            None => return Err(error::Error::from_span(not_closed, open)),
+        }
 
-                    return Ok(Node::from_cl_ln(
-                        expression::All::Arithmetic {
-                            value: expression::Arithmetic::Neg {
-                                value: Box::new(value),
-                            },
-                        },
-                        &cl_ln,
-                    ));
-                }
-                _ => {}
+        values.push(value(parser)?);
+
+        match parser.peek() {
+            Some(token) if token.token_type == lexer::TokenType::Comma => {
+                parser.next();
             }
+            Some(token) if token.token_type == close => {}
+            Some(token) => return Err(unexpected(token)),
+            None => return Err(error::Error::from_span(not_closed, open)),
         }
     }
 
-    macro_rules! literal {
-        ($scope:ident::$type:ident, $t:expr, $v:expr) => {
-            let cl_ln = $t.cl_ln();
-
-            let l = expression::$scope::$type { value: $v };
+    Ok((values, parser.next().unwrap()))
+}
 
-            let expression = Node::from_cl_ln(
-                expression::All::SingleDataUnit {
-                    value: expression::SingleDataUnit::Literal { literal: l },
-                },
-                &cl_ln,
-            );
+/// Parses an expression in parentheses
+///
+/// Assumes that the ( is already consumed
+///
+/// # Example
+/// ```text
+/// (3 + 4)
+/// ```
+fn parse_parentheses(
+    parser: &mut Parser,
+    open: &lexer::Token,
+) -> Result<Node<expression::All>, error::Error<error::ParserErrorType>> {
+    if let Some(close) = parser.eat(&lexer::TokenType::ParenClose) {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::EmptyExpression,
+            &open.combine(close.span()),
+        ));
+    }
 
-            return all(&[&[ExpressionToken::Expression(expression)], &tokens[1..]].concat());
-        };
+    if parser.peek().is_none() {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::UnclosedExpression,
+            open,
+        ));
     }
 
-    match &tokens[0] {
-        ExpressionToken::Expression(e) => {
-            if tokens.len() == 1 {
-                return Ok(e.clone());
-            } else {
-                if let ExpressionToken::Token(t) = &tokens[1] {
-                    match &t.token_type {
-                        lexer::TokenType::Dot => {
-                            return parse_property_access(e.clone(), &tokens[1..]);
-                        }
-                        lexer::TokenType::SquareOpen => {
-                            return parse_array_access(e.clone(), &tokens[1..]);
-                        }
-                        lexer::TokenType::ParenOpen => {
-                            return parse_function_call(e.clone(), &tokens[1..]);
-                        }
-                        lexer::TokenType::CurlyOpen => {
-                            // TODO: Implement struct init
-                            return Err(error::Error::from_cl_ln(
-                                error::ParserErrorType::FeatureNotImplemented(
-                                    "struct init".to_string(),
-                                ),
-                                t,
-                            ));
-                        }
-                        _ => {
-                            return Err(error::Error::from_cl_ln(
-                                error::ParserErrorType::UnexpectedToken(t.token_type.clone()),
-                                t,
-                            ));
-                        }
-                    }
-                }
-            }
-        }
-        ExpressionToken::Token(t) => match &t.token_type {
-            lexer::TokenType::FloatLiteral(f) => {
-                literal!(Literal::Float, t, *f);
-            }
-            lexer::TokenType::IntegerLiteral(i) => {
-                literal!(Literal::Integer, t, *i);
-            }
-            lexer::TokenType::StringLiteral(s) => {
-                literal!(Literal::String, t, s.to_string());
-            }
-            lexer::TokenType::BooleanLiteral(b) => {
-                literal!(Literal::Boolean, t, *b);
-            }
-            lexer::TokenType::SquareOpen => {
-                // TODO: Implement array
-                return Err(error::Error::from_cl_ln(
-                    error::ParserErrorType::FeatureNotImplemented("array init".to_string()),
-                    t,
-                ));
-            }
-            lexer::TokenType::Identifier(_) => {
-                return parse_identifier(tokens);
-            }
-            _ => {
-                return Err(error::Error::from_cl_ln(
-                    error::ParserErrorType::UnexpectedToken(t.token_type.clone()),
-                    t,
-                ));
-            }
-        },
-    };
+    let expression = operations::gen(parser, 0)?;
 
-    panic!("Should never get here");
+    match parser.next() {
+        Some(token) if token.token_type == lexer::TokenType::ParenClose => Ok(expression),
+        Some(token) => Err(unexpected(token)),
+        None => Err(error::Error::from_span(
+            error::ParserErrorType::UnclosedExpression,
+            open,
+        )),
+    }
 }
 
+/// Parses an array access
+///
+/// Assumes that the next token is [
+///
+/// # Example
+/// ```text
+/// a[0]
+/// [1, 2][a + 1]
+/// ```
 fn parse_array_access(
+    parser: &mut Parser,
     expression: Node<expression::All>,
-    tokens: &[ExpressionToken],
 ) -> Result<Node<expression::All>, error::Error<error::ParserErrorType>> {
-    if tokens.len() == 0 {
-        panic!("Cannot parse empty expression");
-    }
+    let open = parser.next().unwrap();
 
-    match &tokens[0] {
-        ExpressionToken::Token(lexer::Token {
-            token_type: lexer::TokenType::SquareOpen,
-            ..
-        }) => {}
-        _ => panic!("First token must be a ["),
+    if let Some(close) = parser.eat(&lexer::TokenType::SquareClose) {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::EmptyExpression,
+            &open.combine(close.span()),
+        ));
     }
 
-    let mut curly_count = 0;
-    let mut square_count = 1;
-    let mut paren_count = 0;
-
-    for i in 1..tokens.len() {
-        if let ExpressionToken::Token(t) = &tokens[i] {
-            match &t.token_type {
-                lexer::TokenType::ParenOpen => paren_count += 1,
-                lexer::TokenType::ParenClose => paren_count -= 1,
-                lexer::TokenType::CurlyOpen => curly_count += 1,
-                lexer::TokenType::CurlyClose => curly_count -= 1,
-                lexer::TokenType::SquareOpen => square_count += 1,
-                lexer::TokenType::SquareClose => {
-                    if square_count == 1 {
-                        if curly_count != 0 || paren_count != 0 {
-                            return Err(error::Error::from_cl_ln(
-                                error::ParserErrorType::UnexpectedCloseSquare,
-                                t,
-                            ));
-                        }
-
-                        let array_access_tokens = &tokens[1..i];
-
-                        if array_access_tokens.len() == 0 {
-                            return Err(error::Error::from_cl_ln(
-                                error::ParserErrorType::EmptyExpression,
-                                &cl_ln::combine(&tokens[1..=i]),
-                            ));
-                        }
-
-                        let cl_ln = cl_ln::combine(
-                            &[
-                                &[ExpressionToken::Expression(expression.clone())],
-                                &tokens[..=i],
-                            ]
-                            .concat(),
-                        );
-
-                        let expression = Node::from_cl_ln(
-                            expression::All::SingleDataUnit {
-                                value: expression::SingleDataUnit::ArrayAccess {
-                                    array: Box::new(expression.clone()),
-                                    index: Box::new(operations::gen(array_access_tokens)?),
-                                },
-                            },
-                            &cl_ln,
-                        );
-
-                        return all(&[
-                            &[ExpressionToken::Expression(expression)],
-                            &tokens[i + 1..],
-                        ]
-                        .concat());
-                    }
-                    square_count -= 1;
-                }
-                _ => {}
-            }
-        }
+    if parser.peek().is_none() {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::SquareNotClosed,
+            open,
+        ));
     }
 
-    match &tokens[0] {
-        ExpressionToken::Token(t) => Err(error::Error::from_cl_ln(
+    let index = operations::gen(parser, 0)?;
+
+    match parser.next() {
+        Some(token) if token.token_type == lexer::TokenType::SquareClose => {
+            let span = expression.combine(token.span());
+
+            Ok(Node::from_span(
+                expression::All::SingleDataUnit {
+                    value: expression::SingleDataUnit::ArrayAccess {
+                        array: Box::new(expression),
+                        index: Box::new(index),
+                    },
+                },
+                &span,
+            ))
+        }
+        Some(token) => Err(unexpected(token)),
+        None => Err(error::Error::from_span(
             error::ParserErrorType::SquareNotClosed,
-            t,
+            open,
         )),
-        _ => panic!("First token must be a ["),
     }
 }
 
-/// Parses property access
+/// Parses an array init, the values are separated by commas and a trailing comma is allowed
 ///
-/// Assumes that first token is .
-///
-/// If there are more tokens after the property access, then the function will call `all` recessively again
+/// Assumes that the [ is already consumed
 ///
 /// # Example
+/// ```text
+/// [1, 2, 3]
+/// [[1], [2, 3],][0]
 /// ```
+fn parse_array_init(
+    parser: &mut Parser,
+    open: &lexer::Token,
+) -> Result<Node<expression::All>, error::Error<error::ParserErrorType>> {
+    let (values, close) = list(
+        parser,
+        open,
+        lexer::TokenType::SquareClose,
+        error::ParserErrorType::SquareNotClosed,
+        |parser| operations::gen(parser, 0),
+    )?;
+
+    Ok(Node::from_span(
+        expression::All::SingleDataUnit {
+            value: expression::SingleDataUnit::ArrayInit { values },
+        },
+        &open.combine(close.span()),
+    ))
+}
+
+/// Parses property access
+///
+/// Assumes that the next token is .
+///
+/// # Example
+/// ```text
 /// test.test
 /// (3 + 1).example
 /// ```
 fn parse_property_access(
+    parser: &mut Parser,
     expression: Node<expression::All>,
-    tokens: &[ExpressionToken],
 ) -> Result<Node<expression::All>, error::Error<error::ParserErrorType>> {
-    if tokens.len() == 0 {
-        panic!("Cannot parse empty expression");
-    }
+    let dot = parser.next().unwrap();
 
-    match &tokens[0] {
-        ExpressionToken::Token(lexer::Token {
-            token_type: lexer::TokenType::Dot,
-            ..
-        }) => {}
-        _ => panic!("First token must be a dot"),
-    }
+    match parser.next() {
+        Some(token) => match &token.token_type {
+            lexer::TokenType::Identifier(property) => {
+                let span = expression.combine(token.span());
 
-    if tokens.len() == 1 {
-        let cl_ln = cl_ln::combine(&[ExpressionToken::Expression(expression), tokens[0].clone()]);
-
-        return Err(error::Error::from_cl_ln(
-            error::ParserErrorType::NoPropertyOnAccess,
-            &cl_ln,
-        ));
-    }
-    if let ExpressionToken::Token(t) = &tokens[1] {
-        match &t.token_type {
-            lexer::TokenType::Identifier(i) => {
-                let cl_ln = cl_ln::combine(
-                    &[
-                        &[ExpressionToken::Expression(expression.clone())],
-                        &tokens[..=1],
-                    ]
-                    .concat(),
-                );
-
-                let expression = Node::from_cl_ln(
+                Ok(Node::from_span(
                     expression::All::SingleDataUnit {
                         value: expression::SingleDataUnit::PropertyAccess {
-                            object: Box::new(expression.clone()),
-                            property: i.to_string(),
+                            object: Box::new(expression),
+                            property: property.to_string(),
                         },
                     },
-                    &cl_ln,
-                );
-
-                return all(&[&[ExpressionToken::Expression(expression)], &tokens[2..]].concat());
+                    &span,
+                ))
             }
-            _ => {
-                return Err(error::Error::from_cl_ln(
-                    error::ParserErrorType::UnexpectedToken(t.token_type.clone()),
-                    t,
-                ));
-            }
-        }
-    } else {
-        Err(error::Error::from_cl_ln(
-            error::ParserErrorType::UnexpectedExpression,
-            &tokens[1],
-        ))
+            token_type => Err(error::Error::from_span(
+                error::ParserErrorType::UnexpectedToken(token_type.clone()),
+                token,
+            )),
+        },
+        None => Err(error::Error::from_span(
+            error::ParserErrorType::NoPropertyOnAccess,
+            &expression.combine(dot.span()),
+        )),
     }
 }
 
-fn parse_function_call(
+/// Parses a struct init
+///
+/// Assumes that the next token is {
+///
+/// # Example
+/// ```text
+/// Point { x: 3, y: 4 }
+/// geo::Point { x: 3, y: 4, }.x
+/// ```
+fn parse_struct_init(
+    parser: &mut Parser,
     expression: Node<expression::All>,
-    tokens: &[ExpressionToken],
 ) -> Result<Node<expression::All>, error::Error<error::ParserErrorType>> {
-    if tokens.len() == 0 {
-        panic!("Cannot parse empty expression");
-    }
+    let open = parser.next().unwrap();
+
+    let (namespace, identifier) = match expression.node {
+        expression::All::SingleDataUnit {
+            value:
+                expression::SingleDataUnit::Identifier {
+                    ref namespace,
+                    ref identifier,
+                },
+        } => (namespace.clone(), identifier.clone()),
+        _ => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::UnexpectedExpression,
+                &expression,
+            ));
+        }
+    };
+
+    let (fields, close) = list(
+        parser,
+        open,
+        lexer::TokenType::CurlyClose,
+        error::ParserErrorType::CurlyNotClosed,
+        parse_struct_field,
+    )?;
+
+    let mut values = HashMap::new();
+
+    for (token, field, value) in fields {
+        if values.contains_key(&field) {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::DuplicateField(field),
+                token,
+            ));
+        }
 
-    match &tokens[0] {
-        ExpressionToken::Token(lexer::Token {
-            token_type: lexer::TokenType::ParenOpen,
-            ..
-        }) => {}
-        _ => panic!("First token must be a ("),
+        values.insert(field, value);
     }
 
-    let mut curly_count = 0;
-    let mut square_count = 0;
-    let mut paren_count = 1;
-
-    let mut start = 1;
-    let mut end = tokens.len();
-    let mut args = vec![];
-    let mut is_out = false;
-
-    for i in 1..tokens.len() {
-        if let ExpressionToken::Token(t) = &tokens[i] {
-            match &t.token_type {
-                lexer::TokenType::ParenOpen => paren_count += 1,
-                lexer::TokenType::ParenClose => {
-                    paren_count -= 1;
-
-                    if curly_count != 0 || square_count != 0 {
-                        return Err(error::Error::from_cl_ln(
-                            error::ParserErrorType::UnexpectedCloseParen,
-                            t,
-                        ));
-                    }
-
-                    if paren_count == 0 {
-                        end = i;
-                        break;
-                    }
-                }
-                lexer::TokenType::CurlyOpen => curly_count += 1,
-                lexer::TokenType::CurlyClose => curly_count -= 1,
-                lexer::TokenType::SquareOpen => square_count += 1,
-                lexer::TokenType::SquareClose => square_count -= 1,
-                lexer::TokenType::Out => {
-                    if start == i {
-                        is_out = true;
-                        start += 1;
-                    } else {
-                        return Err(error::Error::from_cl_ln(
-                            error::ParserErrorType::UnexpectedToken(lexer::TokenType::Out),
-                            t,
-                        ));
-                    }
-                }
-                lexer::TokenType::Comma => {
-                    if paren_count != 1 || curly_count != 0 || square_count != 0 {
-                        continue;
-                    }
-
-                    let exp_tokens = &tokens[start..i];
-
-                    if exp_tokens.len() == 0 {
-                        return Err(error::Error::from_cl_ln(
-                            error::ParserErrorType::EmptyExpression,
-                            &cl_ln::combine(&tokens[i - 1..=i]),
-                        ));
-                    }
-
-                    let expression = operations::gen(exp_tokens)?;
-                    args.push((is_out, expression));
-                    start = i + 1;
-                }
+    Ok(Node::from_span(
+        expression::All::SingleDataUnit {
+            value: expression::SingleDataUnit::StructInit {
+                namespace,
+                identifier,
+                values,
+            },
+        },
+        &expression.combine(close.span()),
+    ))
+}
 
-                _ => {}
-            }
+/// Parses a single field of a struct init, e.g. `x: 3 + 4`. The token of the field name is
+/// returned as well, for errors about duplicate fields.
+fn parse_struct_field<'a>(
+    parser: &mut Parser<'a>,
+) -> Result<(&'a lexer::Token, String, Node<expression::All>), error::Error<error::ParserErrorType>>
+{
+    let token = parser.next().unwrap();
+
+    let field = match &token.token_type {
+        lexer::TokenType::Identifier(s) => s.clone(),
+        _ => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::MissingIdentifier,
+                token,
+            ));
         }
-    }
+    };
 
-    if paren_count != 0 || curly_count != 0 || square_count != 0 {
-        if paren_count != 0 {
-            return Err(error::Error::from_cl_ln(
-                error::ParserErrorType::ParenNotClosed,
-                &tokens[0],
+    let colon = match parser.next() {
+        Some(t) if t.token_type == lexer::TokenType::Colon => t,
+        Some(t) => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::UnexpectedTokenExpected(
+                    t.token_type.clone(),
+                    lexer::TokenType::Colon,
+                ),
+                t,
             ));
-        } else {
-            panic!("Parenthesis not closed, should be handled by the loop above");
         }
-    }
+        None => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::StatementEndEarly,
+                token,
+            ));
+        }
+    };
 
-    let last_arg_tokens = &tokens[start..end];
-    if last_arg_tokens.len() != 0 {
-        args.push((is_out, operations::gen(last_arg_tokens)?));
+    if !parser.at_value() {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::StatementEndEarly,
+            parser.peek().unwrap_or(colon),
+        ));
     }
 
-    let all_tokens = &[
-        &[ExpressionToken::Expression(expression.clone())],
-        &tokens[..=end],
-    ]
-    .concat();
+    Ok((token, field, operations::gen(parser, 0)?))
+}
 
-    let expression = Node::from_cl_ln(
+/// Parses a function call, an argument can be prefixed with `out`
+///
+/// Assumes that the next token is (
+///
+/// # Example
+/// ```text
+/// std::print(3)
+/// std::parse_str("3", out a)
+/// ```
+fn parse_function_call(
+    parser: &mut Parser,
+    expression: Node<expression::All>,
+) -> Result<Node<expression::All>, error::Error<error::ParserErrorType>> {
+    let open = parser.next().unwrap();
+
+    let (arguments, close) = list(
+        parser,
+        open,
+        lexer::TokenType::ParenClose,
+        error::ParserErrorType::ParenNotClosed,
+        |parser| {
+            let is_out = parser.eat(&lexer::TokenType::Out).is_some();
+
+            if !parser.at_value() {
+                return Err(match parser.peek() {
+                    Some(token) => unexpected(token),
+                    None => error::Error::from_span(error::ParserErrorType::ParenNotClosed, open),
+                });
+            }
+
+            Ok((is_out, operations::gen(parser, 0)?))
+        },
+    )?;
+
+    let span = expression.combine(close.span());
+
+    Ok(Node::from_span(
         expression::All::SingleDataUnit {
             value: expression::SingleDataUnit::FunctionCall {
-                function: Box::new(expression.clone()),
-                arguments: args,
+                function: Box::new(expression),
+                arguments,
             },
         },
-        &cl_ln::combine(all_tokens),
-    );
-
-    return all(&[
-        &[ExpressionToken::Expression(expression)],
-        &tokens[end + 1..],
-    ]
-    .concat());
+        &span,
+    ))
 }
 
 /// Parses an identifier
 ///
-/// Assumes that first token is an identifier
+/// Assumes that the first identifier is already consumed
 ///
 /// # Example
-/// ```
+/// ```text
 /// my_function
 /// std::print
 /// std::print::println
 /// ```
-///
 fn parse_identifier(
-    tokens: &[ExpressionToken],
+    parser: &mut Parser,
+    first: &lexer::Token,
 ) -> Result<Node<expression::All>, error::Error<error::ParserErrorType>> {
-    let mut peek_iter = tokens.iter().enumerate().peekable();
-
-    let mut vals: Vec<String> = vec![];
-
-    let mut was_last_double_colon = false;
-
-    while let Some((i, t)) = peek_iter.next() {
-        let t = match t {
-            ExpressionToken::Token(t) => t,
-            ExpressionToken::Expression(e) => {
-                return Err(error::Error::from_cl_ln(
-                    error::ParserErrorType::UnexpectedExpression,
-                    e,
-                ));
-            }
-        };
-
-        match &t.token_type {
-            lexer::TokenType::Identifier(ident) => {
-                vals.push(ident.to_string());
-
-                if i != 0 && !was_last_double_colon {
-                    return Err(error::Error::from_cl_ln(
-                        error::ParserErrorType::UnexpectedToken(lexer::TokenType::Identifier(
-                            ident.to_string(),
-                        )),
-                        t,
-                    ));
-                }
+    let mut vals = vec![];
+    let mut last = first;
 
-                was_last_double_colon = false;
-            }
-            lexer::TokenType::DoubleColon => {
-                was_last_double_colon = true;
+    if let lexer::TokenType::Identifier(ident) = &first.token_type {
+        vals.push(ident.to_string());
+    }
 
-                if i == 0 {
-                    panic!("Double colon cannot be first token in an identifier");
+    while let Some(double_colon) = parser.eat(&lexer::TokenType::DoubleColon) {
+        match parser.next() {
+            Some(token) => match &token.token_type {
+                lexer::TokenType::Identifier(ident) => {
+                    vals.push(ident.to_string());
+                    last = token;
                 }
-
-                if peek_iter.peek().is_none() {
-                    return Err(error::Error::from_cl_ln(
-                        error::ParserErrorType::UnexpectedToken(lexer::TokenType::DoubleColon),
-                        t,
+                token_type => {
+                    return Err(error::Error::from_span(
+                        error::ParserErrorType::UnexpectedToken(token_type.clone()),
+                        token,
                     ));
                 }
-
-                let nt = peek_iter.peek().unwrap().1;
-
-                match nt {
-                    ExpressionToken::Token(lexer::Token {
-                        token_type: lexer::TokenType::Identifier(_),
-                        ..
-                    }) => {}
-                    ExpressionToken::Token(t) => {
-                        return Err(error::Error::from_cl_ln(
-                            error::ParserErrorType::UnexpectedToken(t.token_type.clone()),
-                            t,
-                        ));
-                    }
-                    ExpressionToken::Expression(e) => {
-                        return Err(error::Error::from_cl_ln(
-                            error::ParserErrorType::UnexpectedExpression,
-                            e,
-                        ));
-                    }
-                };
-            }
-            _ => {
-                break;
+            },
+            None => {
+                return Err(error::Error::from_span(
+                    error::ParserErrorType::UnexpectedToken(lexer::TokenType::DoubleColon),
+                    double_colon,
+                ));
             }
-        };
-    }
-
-    if vals.len() == 0 {
-        panic!("Identifier is empty");
+        }
     }
 
-    let namespace = vals[..vals.len() - 1].to_vec();
-    let identifier = vals[vals.len() - 1].clone();
-
-    let end = vals.len() * 2 - 1;
-
-    let cl_ln = cl_ln::combine(&tokens[..end]);
+    let identifier = vals.pop().unwrap();
 
-    let expression = Node::from_cl_ln(
+    Ok(Node::from_span(
         expression::All::SingleDataUnit {
             value: expression::SingleDataUnit::Identifier {
-                namespace,
+                namespace: vals,
                 identifier,
             },
         },
-        &cl_ln,
-    );
-
-    return all(&[&[ExpressionToken::Expression(expression)], &tokens[end..]].concat());
+        &first.combine(last.span()),
+    ))
 }
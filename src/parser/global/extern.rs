@@ -0,0 +1,65 @@
+use super::*;
+
+/// Parses the declaration of a function that is implemented in JavaScript. The string is the
+/// module to import the function from and the name it's exported as, separated by `#`. The name
+/// has to be a JavaScript identifier since it's imported with `import { name as ... }`.
+///
+/// Assumes that the first token is `extern` and that the `;` ending the declaration isn't
+/// included
+///
+/// # Example
+/// ```text
+/// extern fnc now() -> int = "./time.js#now"
+/// ```
+pub fn gen(
+    tokens: &[lexer::Token],
+) -> Result<
+    Node<node::global::All<Node<node::expression::All>>>,
+    error::Error<error::ParserErrorType>,
+> {
+    let (identifier, arguments, return_type, source) = function::binding(tokens)?;
+
+    let (module, export) = match source.rsplit_once('#') {
+        Some((module, export)) if !module.is_empty() && !export.is_empty() => {
+            (module.to_string(), export.to_string())
+        }
+        _ => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::InvalidExternSource(source.clone()),
+                &tokens[tokens.len() - 1],
+            ));
+        }
+    };
+
+    if !is_js_identifier(&export) {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::InvalidExternExport(export),
+            &tokens[tokens.len() - 1],
+        ));
+    }
+
+    Ok(Node::from_span(
+        node::global::All::ExternDeclaration {
+            identifier,
+            arguments,
+            return_type,
+            module,
+            export,
+            doc: None,
+        },
+        &span::combine(tokens),
+    ))
+}
+
+/// Returns if the name is a JavaScript identifier name. Reserved words are allowed, since they can
+/// be imported under another name, e.g. `import { default as a }`.
+fn is_js_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c == '$' || c == '_' || unicode_ident::is_xid_start(c))
+        && chars.all(|c| {
+            c == '$' || c == '\u{200c}' || c == '\u{200d}' || unicode_ident::is_xid_continue(c)
+        })
+}
@@ -0,0 +1,243 @@
+use super::*;
+
+/// Parses a function declaration
+///
+/// Assumes that the first token is `fnc` and that the last token is the `}` closing the body. Errors
+/// in the body are added to `errors`.
+///
+/// # Example
+/// ```text
+/// fnc sum(a: int, b: int) -> int {
+///     ret a + b;
+/// }
+/// fnc parse(input: str, out output: int) -> bln {}
+/// ```
+pub fn gen(
+    tokens: &[lexer::Token],
+    depth: usize,
+    errors: &mut Vec<error::Error<error::ParserErrorType>>,
+) -> Result<
+    Node<node::global::All<Node<node::expression::All>>>,
+    error::Error<error::ParserErrorType>,
+> {
+    // `curly_end` guarantees that there is a `{` somewhere in the tokens
+    let body_start = tokens
+        .iter()
+        .position(|t| t.token_type == lexer::TokenType::CurlyOpen)
+        .unwrap();
+
+    let (identifier, arguments, return_type) = signature(&tokens[..body_start])?;
+
+    check_depth(depth + 1, &tokens[0])?;
+
+    let body = block::gen(&tokens[body_start + 1..tokens.len() - 1], depth + 1, errors);
+
+    Ok(Node::from_span(
+        node::global::All::FunctionDeclaration {
+            identifier,
+            arguments,
+            return_type,
+            body,
+            doc: None,
+        },
+        &span::combine(tokens),
+    ))
+}
+
+/// Parses the name, arguments and return type of a function, i.e. everything before the body.
+/// The return type is `Type::Empty` if it's left out.
+///
+/// Assumes that the first token is `fnc`
+///
+/// # Example
+/// ```text
+/// fnc sum(a: int, b: int) -> int
+/// fnc print(input: str)
+/// ```
+pub fn signature(
+    tokens: &[lexer::Token],
+) -> Result<
+    (String, Vec<node::global::FunctionArgument>, common::Type),
+    error::Error<error::ParserErrorType>,
+> {
+    let identifier = match tokens.get(1).map(|t| &t.token_type) {
+        Some(lexer::TokenType::Identifier(s)) => s.clone(),
+        _ => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::MissingIdentifier,
+                &tokens[0],
+            ));
+        }
+    };
+
+    expect(tokens, 2, lexer::TokenType::ParenOpen)?;
+
+    let paren_close = match tokens
+        .iter()
+        .position(|t| t.token_type == lexer::TokenType::ParenClose)
+    {
+        Some(i) => i,
+        None => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::ParenNotClosed,
+                &tokens[2],
+            ));
+        }
+    };
+
+    let arguments = parse_arguments(&tokens[3..paren_close], &tokens[paren_close])?;
+
+    let return_type_tokens = &tokens[paren_close + 1..];
+
+    let return_type = if return_type_tokens.is_empty() {
+        common::Type::Empty
+    } else {
+        expect(return_type_tokens, 0, lexer::TokenType::RightArrow)?;
+
+        if return_type_tokens.len() == 1 {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::StatementEndEarly,
+                &return_type_tokens[0],
+            ));
+        }
+
+        block::r#type::gen(&return_type_tokens[1..])?
+    };
+
+    Ok((identifier, arguments, return_type))
+}
+
+/// Parses a function declaration without a body that is bound to a string, e.g. an intrinsic or
+/// an extern function. Returns the signature of the function together with the string.
+///
+/// Assumes that the first token is the keyword before `fnc` and that the `;` ending the
+/// declaration isn't included
+///
+/// # Example
+/// ```text
+/// intrinsic fnc sqrt(input: flt) -> flt = "math::sqrt"
+/// extern fnc now() -> int = "./time.js#now"
+/// ```
+pub fn binding(
+    tokens: &[lexer::Token],
+) -> Result<
+    (
+        String,
+        Vec<node::global::FunctionArgument>,
+        common::Type,
+        String,
+    ),
+    error::Error<error::ParserErrorType>,
+> {
+    expect(tokens, 1, lexer::TokenType::Function)?;
+
+    let assign = match tokens
+        .iter()
+        .position(|t| t.token_type == lexer::TokenType::Assign)
+    {
+        Some(i) => i,
+        None => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::StatementEndEarly,
+                &tokens[tokens.len() - 1],
+            ));
+        }
+    };
+
+    let (identifier, arguments, return_type) = signature(&tokens[1..assign])?;
+
+    let string = match &tokens[assign + 1..] {
+        [] => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::StatementEndEarly,
+                &tokens[assign],
+            ));
+        }
+        [t] => match &t.token_type {
+            lexer::TokenType::StringLiteral(s) => s.clone(),
+            token_type => {
+                return Err(error::Error::from_span(
+                    error::ParserErrorType::UnexpectedToken(token_type.clone()),
+                    t,
+                ));
+            }
+        },
+        [_, t, ..] => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::UnexpectedTokenExpected(
+                    t.token_type.clone(),
+                    lexer::TokenType::Semicolon,
+                ),
+                t,
+            ));
+        }
+    };
+
+    Ok((identifier, arguments, return_type, string))
+}
+
+/// Parses the arguments between the parentheses of a function declaration
+///
+/// # Example
+/// ```text
+/// a: int, out b: flt
+/// ```
+fn parse_arguments(
+    tokens: &[lexer::Token],
+    paren_close: &lexer::Token,
+) -> Result<Vec<node::global::FunctionArgument>, error::Error<error::ParserErrorType>> {
+    if tokens.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut arguments = vec![];
+    let mut start = 0;
+
+    for i in 0..=tokens.len() {
+        if i != tokens.len() && tokens[i].token_type != lexer::TokenType::Comma {
+            continue;
+        }
+
+        let arg_tokens = &tokens[start..i];
+        let end_token = tokens.get(i).unwrap_or(paren_close);
+
+        if arg_tokens.is_empty() {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::MissingIdentifier,
+                end_token,
+            ));
+        }
+
+        let is_out = arg_tokens[0].token_type == lexer::TokenType::Out;
+        let arg_tokens = if is_out { &arg_tokens[1..] } else { arg_tokens };
+
+        if arg_tokens.len() < 3 {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::StatementEndEarly,
+                end_token,
+            ));
+        }
+
+        let identifier = match &arg_tokens[0].token_type {
+            lexer::TokenType::Identifier(s) => s.clone(),
+            _ => {
+                return Err(error::Error::from_span(
+                    error::ParserErrorType::MissingIdentifier,
+                    &arg_tokens[0],
+                ));
+            }
+        };
+
+        expect(arg_tokens, 1, lexer::TokenType::Colon)?;
+
+        arguments.push(node::global::FunctionArgument {
+            identifier,
+            ty: block::r#type::gen(&arg_tokens[2..])?,
+            is_out,
+        });
+
+        start = i + 1;
+    }
+
+    Ok(arguments)
+}
@@ -0,0 +1,31 @@
+use super::*;
+
+/// Parses the declaration of a function that is implemented by the compiler, the string is the
+/// id of the intrinsic
+///
+/// Assumes that the first token is `intrinsic` and that the `;` ending the declaration isn't
+/// included
+///
+/// # Example
+/// ```text
+/// intrinsic fnc sqrt(input: flt) -> flt = "math::sqrt"
+/// ```
+pub fn gen(
+    tokens: &[lexer::Token],
+) -> Result<
+    Node<node::global::All<Node<node::expression::All>>>,
+    error::Error<error::ParserErrorType>,
+> {
+    let (identifier, arguments, return_type, id) = function::binding(tokens)?;
+
+    Ok(Node::from_span(
+        node::global::All::IntrinsicDeclaration {
+            identifier,
+            arguments,
+            return_type,
+            id,
+            doc: None,
+        },
+        &span::combine(tokens),
+    ))
+}
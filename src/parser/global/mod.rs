@@ -1 +1,616 @@
-use super::*;
+use super::{node::Node, *};
+
+mod r#extern;
+mod function;
+mod intrinsic;
+mod r#struct;
+
+/// Generates an abstract syntax tree from a list of tokens for the global syntax. That is
+/// everything that can be declared at the top level of a file.
+///
+/// A declaration that fails to parse is replaced by an `Error` node and its error is added to
+/// `errors`, unless the lexer already reported an error in it. Parsing then continues with the next
+/// declaration.
+///
+/// # Examples
+/// ```text
+/// fnc main() {
+///     var a: int = sum(3, 4);
+/// }
+///
+/// fnc sum(a: int, b: int) -> int {
+///     ret a + b;
+/// }
+/// ```
+pub fn gen(
+    tokens: &[lexer::Token],
+    depth: usize,
+    errors: &mut Vec<error::Error<error::ParserErrorType>>,
+) -> node::Global<node::expression::All> {
+    let mut nodes = vec![];
+    let mut start = 0;
+    // The doc comment for the next declaration together with the token it starts at
+    let mut doc: Option<(String, &lexer::Token)> = None;
+
+    while start < tokens.len() {
+        let tokens = &tokens[start..];
+
+        if let lexer::TokenType::DocComment(text) = &tokens[0].token_type {
+            // Doc comments on consecutive lines are joined into one
+            doc = Some(match doc.take() {
+                Some((doc, token)) => (format!("{}\n{}", doc, text), token),
+                None => (text.clone(), &tokens[0]),
+            });
+            start += 1;
+
+            continue;
+        }
+
+        match item(tokens, depth, errors) {
+            Ok((mut node, consumed)) => {
+                if let Some((text, _)) = doc.take() {
+                    set_doc(&mut node.node, text);
+                }
+
+                nodes.push(node);
+                start += consumed;
+            }
+            Err(error) => {
+                let consumed = synchronize(tokens);
+
+                nodes.push(Node::from_span(
+                    node::global::All::Error {},
+                    &span::combine(&tokens[..consumed]),
+                ));
+                if !has_lexer_error(&tokens[..consumed]) {
+                    errors.push(locate_error(error, &tokens[..consumed]));
+                }
+                doc = None;
+                start += consumed;
+            }
+        }
+    }
+
+    if let Some((_, token)) = doc {
+        errors.push(error::Error::from_span(
+            error::ParserErrorType::MisplacedDocComment,
+            token,
+        ));
+    }
+
+    nodes
+}
+
+/// Parses the first declaration in the tokens. Returns the node and how many tokens it consumed.
+/// Errors in the bodies of the declaration are added to `errors`.
+fn item(
+    tokens: &[lexer::Token],
+    depth: usize,
+    errors: &mut Vec<error::Error<error::ParserErrorType>>,
+) -> Result<(node::GlobalNode<node::expression::All>, usize), error::Error<error::ParserErrorType>>
+{
+    match &tokens[0].token_type {
+        lexer::TokenType::Function => {
+            let end = curly_end(tokens)?;
+
+            Ok((function::gen(&tokens[..=end], depth, errors)?, end + 1))
+        }
+        lexer::TokenType::Const => {
+            let end = semicolon_end(tokens)?;
+
+            let (identifier, ty, value) = block::var_dec::parts(&tokens[..end])?;
+
+            Ok((
+                Node::from_span(
+                    node::global::All::ConstantDeclaration {
+                        identifier,
+                        ty,
+                        value,
+                        doc: None,
+                    },
+                    &span::combine(&tokens[..end]),
+                ),
+                end + 1,
+            ))
+        }
+        lexer::TokenType::Intrinsic => {
+            let end = semicolon_end(tokens)?;
+
+            Ok((intrinsic::gen(&tokens[..end])?, end + 1))
+        }
+        lexer::TokenType::Extern => {
+            let end = semicolon_end(tokens)?;
+
+            Ok((r#extern::gen(&tokens[..end])?, end + 1))
+        }
+        lexer::TokenType::Struct => {
+            let end = curly_end(tokens)?;
+
+            Ok((r#struct::gen(&tokens[..=end])?, end + 1))
+        }
+        lexer::TokenType::Namespace => {
+            let end = curly_end(tokens)?;
+
+            Ok((namespace(&tokens[..=end], depth, errors)?, end + 1))
+        }
+        token_type => Err(error::Error::from_span(
+            error::ParserErrorType::UnexpectedToken(token_type.clone()),
+            &tokens[0],
+        )),
+    }
+}
+
+/// Finds where a declaration that failed to parse ends, so that parsing can continue after it.
+/// That's after the `}` ending its body or the `;` ending it, or at the next keyword or doc comment
+/// that starts a declaration.
+fn synchronize(tokens: &[lexer::Token]) -> usize {
+    use lexer::TokenType::*;
+
+    let mut curly_count = 0usize;
+
+    for (i, t) in tokens.iter().enumerate().skip(1) {
+        match t.token_type {
+            CurlyOpen => curly_count += 1,
+            CurlyClose => {
+                curly_count = curly_count.saturating_sub(1);
+
+                if curly_count == 0 {
+                    return i + 1;
+                }
+            }
+            Semicolon if curly_count == 0 => return i + 1,
+            Function | Const | Intrinsic | Extern | Struct | Namespace | DocComment(_)
+                if curly_count == 0 =>
+            {
+                return i;
+            }
+            _ => {}
+        }
+    }
+
+    tokens.len()
+}
+
+/// Attaches a doc comment to a declaration
+fn set_doc(node: &mut node::global::All<Node<node::expression::All>>, text: String) {
+    match node {
+        node::global::All::FunctionDeclaration { doc, .. }
+        | node::global::All::IntrinsicDeclaration { doc, .. }
+        | node::global::All::ExternDeclaration { doc, .. }
+        | node::global::All::Namespace { doc, .. }
+        | node::global::All::ConstantDeclaration { doc, .. }
+        | node::global::All::StructDeclaration { doc, .. } => *doc = Some(text),
+        node::global::All::Error {} => {}
+    }
+}
+
+/// Parses a namespace, the body can contain any global item including other namespaces
+///
+/// Assumes that the first token is `ns` and that the last token is the `}` closing the body. Errors
+/// in the body are added to `errors`.
+///
+/// # Example
+/// ```text
+/// ns std {
+///     ns math {
+///         fnc sqrt(input: flt) -> flt {}
+///     }
+/// }
+/// ```
+fn namespace(
+    tokens: &[lexer::Token],
+    depth: usize,
+    errors: &mut Vec<error::Error<error::ParserErrorType>>,
+) -> Result<
+    Node<node::global::All<Node<node::expression::All>>>,
+    error::Error<error::ParserErrorType>,
+> {
+    let identifier = match tokens.get(1).map(|t| &t.token_type) {
+        Some(lexer::TokenType::Identifier(s)) => s.clone(),
+        _ => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::MissingIdentifier,
+                &tokens[0],
+            ));
+        }
+    };
+
+    if tokens[2].token_type != lexer::TokenType::CurlyOpen {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::UnexpectedTokenExpected(
+                tokens[2].token_type.clone(),
+                lexer::TokenType::CurlyOpen,
+            ),
+            &tokens[2],
+        ));
+    }
+
+    check_depth(depth + 1, &tokens[0])?;
+
+    let body = gen(&tokens[3..tokens.len() - 1], depth + 1, errors);
+
+    Ok(Node::from_span(
+        node::global::All::Namespace {
+            identifier,
+            body,
+            doc: None,
+        },
+        &span::combine(tokens),
+    ))
+}
+
+/// Finds the index of the first `;` in the tokens
+fn semicolon_end(tokens: &[lexer::Token]) -> Result<usize, error::Error<error::ParserErrorType>> {
+    match tokens
+        .iter()
+        .position(|t| t.token_type == lexer::TokenType::Semicolon)
+    {
+        Some(i) => Ok(i),
+        None => Err(error::Error::from_span(
+            error::ParserErrorType::MissingSemicolon,
+            &tokens[0],
+        )),
+    }
+}
+
+/// Returns an error if the token at index `i` isn't of the expected type
+fn expect(
+    tokens: &[lexer::Token],
+    i: usize,
+    expected: lexer::TokenType,
+) -> Result<(), error::Error<error::ParserErrorType>> {
+    match tokens.get(i) {
+        Some(t) if t.token_type == expected => Ok(()),
+        Some(t) => Err(error::Error::from_span(
+            error::ParserErrorType::UnexpectedTokenExpected(t.token_type.clone(), expected),
+            t,
+        )),
+        None => Err(error::Error::from_span(
+            error::ParserErrorType::StatementEndEarly,
+            &tokens[tokens.len() - 1],
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(code: &str) -> Result<node::Global<node::expression::All>, error::ParserErrorType> {
+        let mut errors = vec![];
+        let tree = gen(&lexer::tokenize(code, 0).0, 0, &mut errors);
+
+        match errors.first() {
+            Some(error) => Err(error.error_type().clone()),
+            None => Ok(tree),
+        }
+    }
+
+    #[test]
+    fn function_declaration() {
+        let tree = parse("fnc sum(a: int, out b: flt) -> int { var c: int = a; }").unwrap();
+
+        assert_eq!(tree.len(), 1);
+
+        match &tree[0].node {
+            node::global::All::FunctionDeclaration {
+                identifier,
+                arguments,
+                return_type,
+                body,
+                ..
+            } => {
+                assert_eq!(identifier, "sum");
+                assert_eq!(
+                    arguments,
+                    &vec![
+                        node::global::FunctionArgument {
+                            identifier: "a".to_string(),
+                            ty: common::Type::Integer,
+                            is_out: false,
+                        },
+                        node::global::FunctionArgument {
+                            identifier: "b".to_string(),
+                            ty: common::Type::Float,
+                            is_out: true,
+                        },
+                    ]
+                );
+                assert_eq!(*return_type, common::Type::Integer);
+                assert_eq!(body.len(), 1);
+            }
+            _ => panic!("Expected a function declaration"),
+        }
+    }
+
+    #[test]
+    fn function_without_return_type_is_empty() {
+        let tree = parse("fnc main() {} fnc other() {}").unwrap();
+
+        assert_eq!(tree.len(), 2);
+
+        match &tree[0].node {
+            node::global::All::FunctionDeclaration { return_type, .. } => {
+                assert_eq!(*return_type, common::Type::Empty);
+            }
+            _ => panic!("Expected a function declaration"),
+        }
+    }
+
+    #[test]
+    fn nested_namespaces() {
+        let tree = parse("ns std { fnc print(input: str) {} ns math { } }").unwrap();
+
+        match &tree[0].node {
+            node::global::All::Namespace {
+                identifier, body, ..
+            } => {
+                assert_eq!(identifier, "std");
+                assert_eq!(body.len(), 2);
+                assert!(matches!(
+                    &body[1].node,
+                    node::global::All::Namespace { identifier, body, .. }
+                        if identifier == "math" && body.is_empty()
+                ));
+            }
+            _ => panic!("Expected a namespace"),
+        }
+    }
+
+    #[test]
+    fn struct_declaration() {
+        let tree = parse("struct Line { start: geo::Point, length: flt, }").unwrap();
+
+        match &tree[0].node {
+            node::global::All::StructDeclaration {
+                identifier, fields, ..
+            } => {
+                assert_eq!(identifier, "Line");
+                assert_eq!(
+                    fields,
+                    &vec![
+                        node::global::StructField {
+                            identifier: "start".to_string(),
+                            ty: common::Type::Struct {
+                                namespace: vec!["geo".to_string()],
+                                identifier: "Point".to_string(),
+                            },
+                        },
+                        node::global::StructField {
+                            identifier: "length".to_string(),
+                            ty: common::Type::Float,
+                        },
+                    ]
+                );
+            }
+            _ => panic!("Expected a struct declaration"),
+        }
+    }
+
+    #[test]
+    fn struct_field_without_type() {
+        assert!(matches!(
+            parse("struct Point { x, y: int }"),
+            Err(error::ParserErrorType::StatementEndEarly)
+        ));
+    }
+
+    #[test]
+    fn constant_in_namespace() {
+        let tree = parse("ns math { cst PI: flt = 3.14; }").unwrap();
+
+        match &tree[0].node {
+            node::global::All::Namespace { body, .. } => assert!(matches!(
+                &body[0].node,
+                node::global::All::ConstantDeclaration { identifier, ty: Some(common::Type::Float), .. }
+                    if identifier == "PI"
+            )),
+            _ => panic!("Expected a namespace"),
+        }
+    }
+
+    #[test]
+    fn intrinsic_declaration() {
+        let tree = parse("intrinsic fnc sqrt(input: flt) -> flt = \"math::sqrt\";").unwrap();
+
+        assert!(matches!(
+            &tree[0].node,
+            node::global::All::IntrinsicDeclaration { identifier, return_type: common::Type::Float, id, .. }
+                if identifier == "sqrt" && id == "math::sqrt"
+        ));
+        assert!(matches!(
+            parse("intrinsic fnc print(input: str) = print;"),
+            Err(error::ParserErrorType::UnexpectedToken(..))
+        ));
+        assert!(matches!(
+            parse("intrinsic fnc print(input: str) = \"print-str\""),
+            Err(error::ParserErrorType::MissingSemicolon)
+        ));
+    }
+
+    #[test]
+    fn extern_declaration() {
+        let tree = parse("extern fnc now() -> int = \"./time.js#now\";").unwrap();
+
+        assert!(matches!(
+            &tree[0].node,
+            node::global::All::ExternDeclaration { identifier, module, export, .. }
+                if identifier == "now" && module == "./time.js" && export == "now"
+        ));
+        assert!(matches!(
+            parse("extern fnc now() -> int = \"./time.js\";"),
+            Err(error::ParserErrorType::InvalidExternSource(..))
+        ));
+        assert!(matches!(
+            parse("extern fnc now() -> int = \"./time.js#now } from 'x'; //\";"),
+            Err(error::ParserErrorType::InvalidExternExport(..))
+        ));
+        assert!(matches!(
+            parse("extern fnc now() -> int = \"./time.js#1now\";"),
+            Err(error::ParserErrorType::InvalidExternExport(..))
+        ));
+        assert!(parse("extern fnc now() -> int = \"./time.js#default\";").is_ok());
+        assert!(parse("extern fnc now() -> int = \"./time.js#$_größe1\";").is_ok());
+
+        // A combining mark is alphabetic but can't start an identifier, and connector punctuation
+        // other than `_` can continue one
+        assert!(matches!(
+            parse("extern fnc now() -> int = \"./time.js#\u{345}now\";"),
+            Err(error::ParserErrorType::InvalidExternExport(..))
+        ));
+        assert!(parse("extern fnc now() -> int = \"./time.js#now\u{203f}ms\";").is_ok());
+    }
+
+    #[test]
+    fn doc_comments() {
+        let tree = parse(
+            "/// Math functions\n\
+             ns math {\n\
+                 /// The square of a number.\n\
+                 ///\n\
+                 /// Same as `a * a`\n\
+                 fnc square(a: int) -> int { ret a * a; }\n\
+                 fnc cube(a: int) -> int { ret a * a * a; }\n\
+             }",
+        )
+        .unwrap();
+
+        match &tree[0].node {
+            node::global::All::Namespace { body, doc, .. } => {
+                assert_eq!(doc.as_deref(), Some("Math functions"));
+                assert!(matches!(
+                    &body[0].node,
+                    node::global::All::FunctionDeclaration { doc: Some(doc), .. }
+                        if doc == "The square of a number.\n\nSame as `a * a`"
+                ));
+                assert!(matches!(
+                    &body[1].node,
+                    node::global::All::FunctionDeclaration { doc: None, .. }
+                ));
+            }
+            _ => panic!("Expected a namespace"),
+        }
+
+        assert!(matches!(
+            parse("fnc main() {}\n/// Nothing to document"),
+            Err(error::ParserErrorType::MisplacedDocComment)
+        ));
+        assert!(matches!(
+            parse("fnc main() {\n/// A variable\nvar a = 1;\n}"),
+            Err(error::ParserErrorType::MisplacedDocComment)
+        ));
+    }
+
+    #[test]
+    fn unclosed_function() {
+        assert!(matches!(
+            parse("fnc main() { var a: int = 3;"),
+            Err(error::ParserErrorType::CurlyNotClosed)
+        ));
+    }
+
+    #[test]
+    fn statement_outside_function() {
+        assert!(matches!(
+            parse("var a: int = 3;"),
+            Err(error::ParserErrorType::UnexpectedToken(
+                lexer::TokenType::Var
+            ))
+        ));
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let nested = |depth: usize| format!("{}{}", "ns a { ".repeat(depth), "} ".repeat(depth));
+
+        assert!(parse(&nested(MAX_NESTING_DEPTH)).is_ok());
+        assert!(matches!(
+            parse(&nested(MAX_NESTING_DEPTH + 1)),
+            Err(error::ParserErrorType::NestingTooDeep)
+        ));
+        assert!(matches!(
+            parse(&format!(
+                "{}fnc b() {{ c; }}{}",
+                "ns a { ".repeat(MAX_NESTING_DEPTH),
+                "} ".repeat(MAX_NESTING_DEPTH)
+            )),
+            Err(error::ParserErrorType::NestingTooDeep)
+        ));
+
+        // So are the types of arguments, return values, fields and constants
+        let array = |depth: usize| format!("{}int{}", "[".repeat(depth), "]".repeat(depth));
+        for declaration in [
+            "fnc f(a: T) {}",
+            "fnc f(out a: T) {}",
+            "fnc f() -> T {}",
+            "struct S { a: T }",
+            "cst A: T = a;",
+        ] {
+            assert!(parse(&declaration.replace('T', &array(MAX_NESTING_DEPTH))).is_ok());
+            assert!(matches!(
+                parse(&declaration.replace('T', &array(MAX_NESTING_DEPTH + 1))),
+                Err(error::ParserErrorType::NestingTooDeep)
+            ));
+        }
+    }
+
+    #[test]
+    fn error_recovery() {
+        let mut errors = vec![];
+        let tree = gen(
+            &lexer::tokenize(
+                "fnc a() { b = ; c(); if d { e +; } } \
+                 struct F { g int } \
+                 /// Doc\n\
+                 cst H = ; \
+                 fnc i() {} \
+                 ns j { fnc k( {} cst l = 1; }",
+                0,
+            )
+            .0,
+            0,
+            &mut errors,
+        );
+
+        assert!(matches!(
+            errors
+                .iter()
+                .map(|e| e.error_type().clone())
+                .collect::<Vec<_>>()
+                .as_slice(),
+            [
+                error::ParserErrorType::StatementEndEarly,
+                error::ParserErrorType::CannotPerformOperationOnEmpty(lexer::TokenType::Add),
+                error::ParserErrorType::StatementEndEarly,
+                error::ParserErrorType::StatementEndEarly,
+                _,
+            ]
+        ));
+
+        // The statements and declarations with errors are replaced, everything else is kept
+        match &tree[0].node {
+            node::global::All::FunctionDeclaration { body, .. } => {
+                assert!(matches!(body[0].node, node::block::All::Error {}));
+                assert!(matches!(body[1].node, node::block::All::Expression { .. }));
+                assert!(matches!(
+                    &body[2].node,
+                    node::block::All::If { then_body, .. }
+                        if matches!(then_body[0].node, node::block::All::Error {})
+                ));
+            }
+            _ => panic!("Expected a function declaration"),
+        }
+        assert!(matches!(tree[1].node, node::global::All::Error {}));
+        assert!(matches!(tree[2].node, node::global::All::Error {}));
+        assert!(matches!(
+            &tree[3].node,
+            node::global::All::FunctionDeclaration { identifier, doc: None, .. } if identifier == "i"
+        ));
+        assert!(matches!(
+            &tree[4].node,
+            node::global::All::Namespace { body, .. }
+                if matches!(body[0].node, node::global::All::Error {})
+                    && matches!(body[1].node, node::global::All::ConstantDeclaration { .. })
+        ));
+    }
+}
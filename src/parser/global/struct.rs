@@ -0,0 +1,80 @@
+use super::*;
+
+/// Parses a struct declaration, the fields are separated by commas and a trailing comma is allowed
+///
+/// Assumes that the first token is `struct` and that the last token is the `}` closing the body
+///
+/// # Example
+/// ```text
+/// struct Point {
+///     x: flt,
+///     y: flt,
+/// }
+/// ```
+pub fn gen(
+    tokens: &[lexer::Token],
+) -> Result<
+    Node<node::global::All<Node<node::expression::All>>>,
+    error::Error<error::ParserErrorType>,
+> {
+    let identifier = match tokens.get(1).map(|t| &t.token_type) {
+        Some(lexer::TokenType::Identifier(s)) => s.clone(),
+        _ => {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::MissingIdentifier,
+                &tokens[0],
+            ));
+        }
+    };
+
+    expect(tokens, 2, lexer::TokenType::CurlyOpen)?;
+
+    let body = &tokens[3..tokens.len() - 1];
+    let mut fields: Vec<node::global::StructField> = vec![];
+
+    for field_tokens in body.split(|t| t.token_type == lexer::TokenType::Comma) {
+        if field_tokens.is_empty() {
+            continue;
+        }
+
+        if field_tokens.len() < 3 {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::StatementEndEarly,
+                &span::combine(field_tokens),
+            ));
+        }
+
+        let field = match &field_tokens[0].token_type {
+            lexer::TokenType::Identifier(s) => s.clone(),
+            _ => {
+                return Err(error::Error::from_span(
+                    error::ParserErrorType::MissingIdentifier,
+                    &field_tokens[0],
+                ));
+            }
+        };
+
+        if fields.iter().any(|f| f.identifier == field) {
+            return Err(error::Error::from_span(
+                error::ParserErrorType::DuplicateField(field),
+                &field_tokens[0],
+            ));
+        }
+
+        expect(field_tokens, 1, lexer::TokenType::Colon)?;
+
+        fields.push(node::global::StructField {
+            identifier: field,
+            ty: block::r#type::gen(&field_tokens[2..])?,
+        });
+    }
+
+    Ok(Node::from_span(
+        node::global::All::StructDeclaration {
+            identifier,
+            fields,
+            doc: None,
+        },
+        &span::combine(tokens),
+    ))
+}
@@ -3,16 +3,115 @@
 use crate::*;
 
 pub mod node;
-use node::Node;
 
 mod block;
 mod expression;
+mod global;
 
+/// How deep blocks, namespaces, parentheses and array types can be nested. Every level of nesting is
+/// parsed recursively, so without a limit deeply nested code would overflow the stack.
+pub const MAX_NESTING_DEPTH: usize = 64;
+
+/// Generates the syntax tree. Parsing doesn't stop at the first syntax error, the statement or
+/// declaration with the error is replaced by an `Error` node and parsing continues after it. Every
+/// error is returned together with the tree, so the static analyzer can still check the parts
+/// without errors.
 pub fn gen(
-    tokens: &Vec<lexer::Token>,
-) -> Result<
-    Vec<Node<node::block::All<Node<node::expression::All>>>>,
-    error::Error<error::ParserErrorType>,
-> {
-    block::gen(&tokens)
+    tokens: &[lexer::Token],
+) -> (
+    node::Global<node::expression::All>,
+    Vec<error::Error<error::ParserErrorType>>,
+) {
+    let mut errors = vec![];
+    let tree = global::gen(tokens, 0, &mut errors);
+
+    (tree, errors)
+}
+
+/// Returns an error if the depth is deeper than `MAX_NESTING_DEPTH`, the token is where the nesting
+/// starts
+fn check_depth<C: span::Spanned>(
+    depth: usize,
+    token: &C,
+) -> Result<(), error::Error<error::ParserErrorType>> {
+    if depth > MAX_NESTING_DEPTH {
+        return Err(error::Error::from_span(
+            error::ParserErrorType::NestingTooDeep,
+            token,
+        ));
+    }
+
+    Ok(())
+}
+
+/// The span of an error from `empty_error`, before it's given the span of the statement or
+/// declaration it's in. It's in a file that doesn't exist, so that it can't be mistaken for a real
+/// span.
+const EMPTY_SPAN: span::Span = span::Span {
+    file_id: usize::MAX,
+    lo: 0,
+    hi: 0,
+};
+
+/// The error for tokens that are empty where something has to be. Callers report missing code
+/// themselves where they have a token to point at, so this is only a fallback. Since there are no
+/// tokens to get a span from, it's located by `locate_error` once the statement or declaration it's
+/// in is known.
+fn empty_error(error_type: error::ParserErrorType) -> error::Error<error::ParserErrorType> {
+    error::Error::new(error_type, EMPTY_SPAN)
+}
+
+/// Gives an error from `empty_error` the span of the last token of the statement or declaration it's
+/// in, since that's where the code ended early
+fn locate_error(
+    error: error::Error<error::ParserErrorType>,
+    tokens: &[lexer::Token],
+) -> error::Error<error::ParserErrorType> {
+    use span::Spanned;
+
+    match tokens.last() {
+        Some(token) if error.span() == EMPTY_SPAN => {
+            error::Error::from_span(error.error_type().clone(), token)
+        }
+        _ => error,
+    }
+}
+
+/// Returns if any of the tokens is an `Error` token. The lexer has already reported the error, so a
+/// statement or declaration containing one fails to parse without reporting another error.
+fn has_lexer_error(tokens: &[lexer::Token]) -> bool {
+    tokens
+        .iter()
+        .any(|t| t.token_type == lexer::TokenType::Error)
+}
+
+/// Finds the index of the `}` that closes the first `{` in the tokens
+fn curly_end(tokens: &[lexer::Token]) -> Result<usize, error::Error<error::ParserErrorType>> {
+    let mut curly_count = 0;
+
+    for (i, t) in tokens.iter().enumerate() {
+        match t.token_type {
+            lexer::TokenType::CurlyOpen => curly_count += 1,
+            lexer::TokenType::CurlyClose => {
+                curly_count -= 1;
+
+                if curly_count < 0 {
+                    return Err(error::Error::from_span(
+                        error::ParserErrorType::UnexpectedCloseCurly,
+                        t,
+                    ));
+                }
+
+                if curly_count == 0 {
+                    return Ok(i);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Err(error::Error::from_span(
+        error::ParserErrorType::CurlyNotClosed,
+        &tokens[0],
+    ))
 }
@@ -4,33 +4,21 @@ use std::fmt::Debug;
 #[derive(Clone, Debug, PartialEq)]
 pub struct Node<T> {
     pub node: T,
-    cl_ln: (usize, usize, usize, usize),
+    span: span::Span,
 }
 
 impl<T> Node<T> {
-    pub fn from_cl_ln<C: cl_ln::ClLn + Sized>(node: T, cl_ln: &C) -> Self {
+    pub fn from_span<C: span::Spanned + Sized>(node: T, span: &C) -> Self {
         Self {
             node,
-            cl_ln: cl_ln.cl_ln(),
+            span: span.span(),
         }
     }
 }
 
-impl<T> cl_ln::ClLn for Node<T> {
-    fn ln_start(&self) -> usize {
-        self.cl_ln.0
-    }
-
-    fn ln_end(&self) -> usize {
-        self.cl_ln.2
-    }
-
-    fn cl_start(&self) -> usize {
-        self.cl_ln.1
-    }
-
-    fn cl_end(&self) -> usize {
-        self.cl_ln.3
+impl<T> span::Spanned for Node<T> {
+    fn span(&self) -> span::Span {
+        self.span
     }
 }
 
@@ -108,7 +96,6 @@ pub mod expression {
     #[derive(Clone, Debug, PartialEq)]
     pub enum All {
         SingleDataUnit { value: SingleDataUnit<Node<All>> },
-        CompilerCustomCodePreDefined { value: String },
         Arithmetic { value: Arithmetic<Node<All>> },
         BooleanLogic { value: BooleanLogic<Node<All>> },
         Cmp { value: Cmp<Node<All>> },
@@ -120,10 +107,6 @@ pub mod expression {
             value: SingleDataUnit<Node<AllWithType>>,
             ty: common::Type,
         },
-        CompilerCustomCodePreDefined {
-            value: String,
-            ty: common::Type,
-        },
         Arithmetic {
             value: Arithmetic<Node<AllWithType>>,
             ty: common::Type,
@@ -142,7 +125,6 @@ pub mod expression {
         pub fn ty(&self) -> common::Type {
             match self {
                 AllWithType::SingleDataUnit { ty, .. } => ty.clone(),
-                AllWithType::CompilerCustomCodePreDefined { ty, .. } => ty.clone(),
                 AllWithType::Arithmetic { ty, .. } => ty.clone(),
                 AllWithType::BooleanLogic { ty, .. } => ty.clone(),
                 AllWithType::Cmp { ty, .. } => ty.clone(),
@@ -161,24 +143,35 @@ pub mod block {
         },
         If {
             cond: T,
-            then_body: Vec<All<T>>,
+            then_body: Vec<Node<All<T>>>,
         },
         IfElse {
             cond: T,
-            then_body: Vec<All<T>>,
-            else_body: Vec<All<T>>,
+            then_body: Vec<Node<All<T>>>,
+            else_body: Vec<Node<All<T>>>,
         },
         While {
             cond: T,
-            body: Vec<All<T>>,
+            body: Vec<Node<All<T>>>,
         },
+        /// The type is `None` when it should be inferred from the value. After static analysis it's
+        /// always `Some`.
         VariableDeclaration {
             identifier: String,
-            ty: common::Type,
+            ty: Option<common::Type>,
             value: T,
         },
-        VariableAssignment {
+        ConstantDeclaration {
             identifier: String,
+            ty: Option<common::Type>,
+            value: T,
+        },
+        /// The target is the place that is assigned to, e.g. `a` or `a.b`. Compound assignments
+        /// have the operator they apply, e.g. `Add` for `a += 1`, so that the target is only
+        /// evaluated once.
+        VariableAssignment {
+            target: T,
+            operator: Option<lexer::TokenType>,
             value: T,
         },
         Return {
@@ -186,5 +179,100 @@ pub mod block {
         },
         Break {},
         Continue {},
+        /// A statement that failed to parse, the parser has already reported the error
+        Error {},
+    }
+
+    /// Returns the if statement if the `else` body is an `else if`, i.e. if it's the only statement
+    /// in the body. Long chains of them are nested deeply, so they're walked in a loop instead of
+    /// recursing into every `else` body.
+    pub fn else_if<T>(else_body: &[Node<All<T>>]) -> Option<&Node<All<T>>> {
+        match else_body {
+            [statement @ Node {
+                node: All::If { .. } | All::IfElse { .. },
+                ..
+            }] => Some(statement),
+            _ => None,
+        }
+    }
+}
+
+pub mod global {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct FunctionArgument {
+        pub identifier: String,
+        pub ty: common::Type,
+        pub is_out: bool,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct StructField {
+        pub identifier: String,
+        pub ty: common::Type,
+    }
+
+    /// Every declaration has the text of the `///` doc comments written right before it, if there
+    /// are any
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum All<T: Sized> {
+        FunctionDeclaration {
+            identifier: String,
+            arguments: Vec<FunctionArgument>,
+            return_type: common::Type,
+            body: Vec<Node<block::All<T>>>,
+            doc: Option<String>,
+        },
+        /// A function of the standard library that is implemented by the compiler, the id refers
+        /// to an `intrinsic::Intrinsic`
+        IntrinsicDeclaration {
+            identifier: String,
+            arguments: Vec<FunctionArgument>,
+            return_type: common::Type,
+            id: String,
+            doc: Option<String>,
+        },
+        /// A function implemented in JavaScript, exported as `export` from `module`
+        ExternDeclaration {
+            identifier: String,
+            arguments: Vec<FunctionArgument>,
+            return_type: common::Type,
+            module: String,
+            export: String,
+            doc: Option<String>,
+        },
+        Namespace {
+            identifier: String,
+            body: Vec<Node<All<T>>>,
+            doc: Option<String>,
+        },
+        /// The type is `None` when it should be inferred from the value. After static analysis it's
+        /// always `Some`.
+        ConstantDeclaration {
+            identifier: String,
+            ty: Option<common::Type>,
+            value: T,
+            doc: Option<String>,
+        },
+        StructDeclaration {
+            identifier: String,
+            fields: Vec<StructField>,
+            doc: Option<String>,
+        },
+        /// A declaration that failed to parse, the parser has already reported the error
+        Error {},
     }
 }
+
+/// A single statement, e.g. a variable declaration
+pub type BlockNode<T> = Node<block::All<Node<T>>>;
+
+/// A list of block nodes, e.g. the body of a function
+pub type Block<T> = Vec<BlockNode<T>>;
+
+/// A single declaration, e.g. a function declaration
+pub type GlobalNode<T> = Node<global::All<Node<T>>>;
+
+/// A list of global nodes, e.g. a whole file
+pub type Global<T> = Vec<GlobalNode<T>>;
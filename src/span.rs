@@ -0,0 +1,291 @@
+//! Locations in the code.
+//!
+//! Tokens, nodes and errors store where they are as a `Span`, a range of bytes in one of the files
+//! in a `SourceMap`. Lines and columns are only worked out by the `SourceMap` when a location is
+//! shown, e.g. in an error message.
+
+use crate::*;
+
+/// The index of a file in a `SourceMap`
+pub type FileId = usize;
+
+/// A range of bytes in a file, `lo` is the first byte and `hi` is the byte after the last one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub file_id: FileId,
+    pub lo: usize,
+    pub hi: usize,
+}
+
+impl Span {
+    pub fn new(file_id: FileId, lo: usize, hi: usize) -> Self {
+        Span { file_id, lo, hi }
+    }
+
+    /// Returns the span from the start of the first of the spans to the end of the last one
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            file_id: self.file_id,
+            lo: usize::min(self.lo, other.lo),
+            hi: usize::max(self.hi, other.hi),
+        }
+    }
+}
+
+pub trait Spanned {
+    fn span(&self) -> Span;
+
+    fn combine<T: Spanned>(&self, other: T) -> Span {
+        self.span().to(other.span())
+    }
+}
+
+impl Spanned for Span {
+    fn span(&self) -> Span {
+        *self
+    }
+}
+
+/// Returns the span covering all of the values, there has to be at least one
+pub fn combine<T: Spanned>(values: &[T]) -> Span {
+    values
+        .iter()
+        .map(Spanned::span)
+        .reduce(Span::to)
+        .expect("Cannot combine no spans")
+}
+
+/// A position in a file, everything is one based. Columns are counted both in characters and in
+/// UTF-16 code units, which is what editors and JavaScript count in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub column_utf16: usize,
+}
+
+/// A file that has been loaded into a `SourceMap`
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+    name: String,
+    code: String,
+    /// The byte offset of the start of every line
+    line_starts: Vec<usize>,
+    /// Every character that is more than one byte, so that characters and UTF-16 code units can be
+    /// counted without going through the code
+    wide_chars: Vec<WideChar>,
+}
+
+/// A character that is more than one byte, with how many more bytes the code has than characters
+/// and than UTF-16 code units up to and including it
+#[derive(Clone, Copy, Debug)]
+struct WideChar {
+    offset: usize,
+    extra_bytes: usize,
+    extra_utf16: usize,
+}
+
+impl SourceFile {
+    fn new(name: &str, code: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(code.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        let mut wide_chars = vec![];
+        let (mut extra_bytes, mut extra_utf16) = (0, 0);
+        for (offset, c) in code.char_indices().filter(|(_, c)| !c.is_ascii()) {
+            extra_bytes += c.len_utf8() - 1;
+            extra_utf16 += c.len_utf8() - c.len_utf16();
+            wide_chars.push(WideChar {
+                offset,
+                extra_bytes,
+                extra_utf16,
+            });
+        }
+
+        SourceFile {
+            name: name.to_string(),
+            code: code.to_string(),
+            line_starts,
+            wide_chars,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Returns the position of the character at the byte offset. A byte order mark at the start of
+    /// the file isn't counted, and neither is the `\r` of a Windows line ending since it comes
+    /// last on its line. Only the lines and the wide characters are searched, so it doesn't take
+    /// longer on long lines.
+    pub fn position(&self, offset: usize) -> Position {
+        let mut offset = offset.min(self.code.len());
+        while !self.code.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let start = self.line_starts[line - 1];
+
+        let (chars, utf16) = self.count(offset);
+        let (start_chars, start_utf16) = self.count(start);
+        let bom = usize::from(start == 0 && offset > 0 && self.code.starts_with('\u{feff}'));
+
+        Position {
+            line,
+            column: chars - start_chars - bom + 1,
+            column_utf16: utf16 - start_utf16 - bom + 1,
+        }
+    }
+
+    /// Returns how many characters and UTF-16 code units come before the byte offset, which has to
+    /// be at the start of a character
+    fn count(&self, offset: usize) -> (usize, usize) {
+        match self.wide_chars.partition_point(|c| c.offset < offset) {
+            0 => (offset, offset),
+            i => {
+                let c = self.wide_chars[i - 1];
+                (offset - c.extra_bytes, offset - c.extra_utf16)
+            }
+        }
+    }
+}
+
+/// Every file that is being compiled, so that spans can be turned back into lines and columns
+#[derive(Clone, Debug)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    /// Creates a source map with the standard library loaded. File id 0 is always reserved for it,
+    /// since its syntax tree is parsed once and shared, even when freestanding code that doesn't use
+    /// it is compiled. The files that are added after it start at 1.
+    pub fn new() -> Self {
+        let mut source_map = SourceMap { files: vec![] };
+        let file_id = source_map.add_file("std.zy", static_analyzer::prelude::SOURCE);
+        debug_assert_eq!(file_id, static_analyzer::prelude::FILE_ID);
+
+        source_map
+    }
+
+    /// Loads a file and returns the id its spans should have
+    pub fn add_file(&mut self, name: &str, code: &str) -> FileId {
+        self.files.push(SourceFile::new(name, code));
+        self.files.len() - 1
+    }
+
+    pub fn file(&self, file_id: FileId) -> &SourceFile {
+        &self.files[file_id]
+    }
+
+    /// Returns the positions of the first and the last character of the span
+    pub fn resolve(&self, span: Span) -> (Position, Position) {
+        let file = self.file(span.file_id);
+        let last = file
+            .code
+            .get(span.lo..span.hi)
+            .and_then(|text| text.char_indices().next_back())
+            .map_or(span.lo, |(i, _)| span.lo + i);
+
+        (file.position(span.lo), file.position(last))
+    }
+
+    /// Formats where the span is, e.g. `main.zy ln: 3 cl: 5-9`
+    pub fn location(&self, span: Span) -> String {
+        let (start, end) = self.resolve(span);
+
+        let range = |start: usize, end: usize| {
+            if start == end {
+                format!("{}", start)
+            } else {
+                format!("{}-{}", start, end)
+            }
+        };
+
+        format!(
+            "{} ln: {} cl: {}",
+            self.file(span.file_id).name(),
+            range(start.line, end.line),
+            range(start.column, end.column)
+        )
+    }
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions() {
+        let mut source_map = SourceMap::new();
+        let file_id = source_map.add_file("main.zy", "\u{feff}a = 1;\r\nb = \"🦀\" + c;\n");
+        let file = source_map.file(file_id);
+
+        let position = |offset| {
+            let Position {
+                line,
+                column,
+                column_utf16,
+            } = file.position(offset);
+            (line, column, column_utf16)
+        };
+
+        assert_eq!(position(3), (1, 1, 1));
+        assert_eq!(position(8), (1, 6, 6));
+        assert_eq!(position(11), (2, 1, 1));
+        assert_eq!(position(16), (2, 6, 6));
+        // After the crab, which is one character but two UTF-16 code units
+        assert_eq!(position(20), (2, 7, 8));
+        assert_eq!(position(24), (2, 11, 12));
+    }
+
+    #[test]
+    fn wide_characters() {
+        let code = "\u{feff}é🦀a\n日本 b\r\n\n🦀🦀x";
+        let file = SourceFile::new("main.zy", code);
+
+        // Counted the slow way, from the start of the line
+        for (offset, _) in code.char_indices() {
+            let start = code[..offset].rfind('\n').map_or(0, |i| i + 1);
+            let text = &code[start..offset];
+            let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+
+            let position = file.position(offset);
+            assert_eq!(position.column, text.chars().count() + 1);
+            assert_eq!(position.column_utf16, text.encode_utf16().count() + 1);
+        }
+    }
+
+    #[test]
+    fn multi_line_spans() {
+        let mut source_map = SourceMap::new();
+        let code = "fnc main() {\n    a;\n  }";
+        let file_id = source_map.add_file("main.zy", code);
+
+        let open = Span::new(file_id, 0, 3);
+        let close = Span::new(file_id, code.len() - 1, code.len());
+
+        assert_eq!(source_map.location(open), "main.zy ln: 1 cl: 1-3");
+        assert_eq!(
+            source_map.location(combine(&[close, open])),
+            "main.zy ln: 1-3 cl: 1-3"
+        );
+        assert_eq!(
+            source_map.location(Span::new(file_id, 17, 19)),
+            "main.zy ln: 2 cl: 5-6"
+        );
+        assert_eq!(source_map.file(0).name(), "std.zy");
+    }
+}
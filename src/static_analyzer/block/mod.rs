@@ -1,10 +1,10 @@
 use super::*;
 
 pub fn check(
-    tree: &Vec<Node<parser::node::block::All<Node<parser::node::expression::All>>>>,
+    tree: &parser::node::Block<parser::node::expression::All>,
     scope: &mut static_analyzer::Scope,
 ) -> Result<
-    Vec<Node<parser::node::block::All<Node<parser::node::expression::AllWithType>>>>,
+    parser::node::Block<parser::node::expression::AllWithType>,
     Vec<error::Error<error::StaticAnalyzerErrorType>>,
 > {
     let mut ret_blocks = vec![];
@@ -16,71 +16,523 @@ pub fn check(
                 identifier,
                 ty,
                 value,
+            }
+            | parser::node::block::All::ConstantDeclaration {
+                identifier,
+                ty,
+                value,
             } => {
+                let mutable = matches!(
+                    node.node,
+                    parser::node::block::All::VariableDeclaration { .. }
+                );
+
                 if scope.has_variable(identifier) {
-                    errors.push(error::Error::from_cl_ln(
+                    errors.push(error::Error::from_span(
                         error::StaticAnalyzerErrorType::VariableAlreadyDeclared(identifier.clone()),
-                        &node.cl_ln(),
+                        &node.span(),
                     ));
                     continue;
                 }
 
-                scope.set_variable(&identifier, ty.clone());
+                let ty = match ty {
+                    Some(ty) => match scope.resolve_type(ty) {
+                        Some(ty) => Some(ty),
+                        None => {
+                            errors.push(error::Error::from_span(
+                                error::StaticAnalyzerErrorType::TypeNotDefined(ty.clone()),
+                                node,
+                            ));
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
 
-                let exp = expression::evaluate(&value, &scope);
+                let (ty, value) = match check_declaration(ty.as_ref(), value, scope) {
+                    Ok(checked) => checked,
+                    Err(errs) => {
+                        errors.extend(errs);
 
-                if let Err(errs) = exp {
-                    errors.extend(errs);
+                        // The variable is still declared if the type is known, so that every use
+                        // of it doesn't report an error
+                        if let Some(ty) = ty {
+                            scope.set_variable(identifier, ty, mutable);
+                        }
+                        continue;
+                    }
+                };
+
+                scope.set_variable(identifier, ty.clone(), mutable);
+
+                let identifier = identifier.clone();
+                let ty = Some(ty);
+
+                ret_blocks.push(Node::from_span(
+                    if mutable {
+                        parser::node::block::All::VariableDeclaration {
+                            identifier,
+                            ty,
+                            value,
+                        }
+                    } else {
+                        parser::node::block::All::ConstantDeclaration {
+                            identifier,
+                            ty,
+                            value,
+                        }
+                    },
+                    node,
+                ));
+            }
+            parser::node::block::All::VariableAssignment {
+                target: place,
+                operator,
+                value,
+            } => {
+                let (namespace, identifier) = assigned_variable(place);
+
+                if scope
+                    .resolve_variable(namespace, identifier)
+                    .is_some_and(|(_, variable)| !variable.mutable)
+                {
+                    errors.push(error::Error::from_span(
+                        error::StaticAnalyzerErrorType::CannotAssignToConstant(
+                            identifier.to_string(),
+                        ),
+                        node,
+                    ));
                     continue;
                 }
 
-                let exp_ty = exp.unwrap();
+                let target = match expression::evaluate(place, scope) {
+                    Ok(target) => target,
+                    Err(errs) => {
+                        errors.extend(errs);
+                        continue;
+                    }
+                };
+                let ty = target.node.ty();
 
-                if exp_ty.node.ty() != *ty {
-                    errors.push(error::Error::from_cl_ln(
-                        error::StaticAnalyzerErrorType::TypeMismatchAssign(
-                            ty.clone(),
-                            exp_ty.node.ty(),
-                        ),
-                        &exp_ty,
+                // A compound assignment is checked as the operation it does, e.g. `a += 1` as
+                // `a + 1`, which has to give a value of the same type as `a`
+                let checked = match operator {
+                    Some(operator) => expression::evaluate(
+                        &compound_value(place, operator, value, node.span()),
+                        scope,
+                    ),
+                    None => expression::evaluate_expecting(value, scope, &ty),
+                };
+
+                let checked = match checked {
+                    Ok(checked) => checked,
+                    Err(errs) => {
+                        errors.extend(errs);
+                        continue;
+                    }
+                };
+
+                if checked.node.ty() != ty {
+                    errors.push(error::Error::from_span(
+                        error::StaticAnalyzerErrorType::TypeMismatchAssign(ty, checked.node.ty()),
+                        &checked,
                     ));
+                    continue;
                 }
 
-                ret_blocks.push(Node::from_cl_ln(
-                    parser::node::block::All::VariableDeclaration {
-                        identifier: identifier.clone(),
-                        ty: ty.clone(),
-                        value: exp_ty,
+                let exp = match operator {
+                    Some(_) => match expression::evaluate(value, scope) {
+                        Ok(exp) => exp,
+                        Err(errs) => {
+                            errors.extend(errs);
+                            continue;
+                        }
+                    },
+                    None => checked,
+                };
+
+                ret_blocks.push(Node::from_span(
+                    parser::node::block::All::VariableAssignment {
+                        target,
+                        operator: operator.clone(),
+                        value: exp,
                     },
                     node,
                 ));
             }
             parser::node::block::All::Expression { value, .. } => {
-                let exp = expression::evaluate(&value, &scope);
+                let exp = expression::evaluate(value, scope);
 
                 if let Err(errs) = exp {
                     errors.extend(errs);
                 } else {
-                    ret_blocks.push(Node::from_cl_ln(
+                    ret_blocks.push(Node::from_span(
                         parser::node::block::All::Expression {
                             value: exp.unwrap(),
                         },
-                        &value.cl_ln(),
+                        &value.span(),
+                    ));
+                }
+            }
+            parser::node::block::All::If { .. } | parser::node::block::All::IfElse { .. } => {
+                match check_if(node, scope) {
+                    Ok(node) => ret_blocks.push(node),
+                    Err(errs) => errors.extend(errs),
+                }
+            }
+            parser::node::block::All::While { cond, body } => {
+                let cond = check_cond(cond, scope);
+
+                let mut body_scope = static_analyzer::Scope::new(Some(scope));
+                body_scope.set_in_loop(true);
+                let body = check(body, &mut body_scope);
+
+                match (cond, body) {
+                    (Ok(cond), Ok(body)) => ret_blocks.push(Node::from_span(
+                        parser::node::block::All::While { cond, body },
+                        node,
+                    )),
+                    (cond, body) => {
+                        errors.extend(cond.err().unwrap_or_default());
+                        errors.extend(body.err().unwrap_or_default());
+                    }
+                }
+            }
+            parser::node::block::All::Break {} | parser::node::block::All::Continue {} => {
+                let (token_type, block) = match &node.node {
+                    parser::node::block::All::Break {} => {
+                        (lexer::TokenType::Break, parser::node::block::All::Break {})
+                    }
+                    _ => (
+                        lexer::TokenType::Continue,
+                        parser::node::block::All::Continue {},
+                    ),
+                };
+
+                if !scope.in_loop() {
+                    errors.push(error::Error::from_span(
+                        error::StaticAnalyzerErrorType::OutsideOfLoop(token_type),
+                        node,
                     ));
+                    continue;
                 }
+
+                ret_blocks.push(Node::from_span(block, node));
+            }
+            parser::node::block::All::Return { value } => {
+                let return_type = scope.return_type().unwrap_or(common::Type::Empty);
+
+                let value = match (value, return_type) {
+                    (None, common::Type::Empty) => None,
+                    (None, ty) => {
+                        errors.push(error::Error::from_span(
+                            error::StaticAnalyzerErrorType::MissingReturnValue(ty),
+                            node,
+                        ));
+                        continue;
+                    }
+                    (Some(_), common::Type::Empty) => {
+                        errors.push(error::Error::from_span(
+                            error::StaticAnalyzerErrorType::ReturnValueInEmptyFunction,
+                            node,
+                        ));
+                        continue;
+                    }
+                    (Some(value), ty) => {
+                        let exp = match expression::evaluate_expecting(value, scope, &ty) {
+                            Ok(exp) => exp,
+                            Err(errs) => {
+                                errors.extend(errs);
+                                continue;
+                            }
+                        };
+
+                        if exp.node.ty() != ty {
+                            errors.push(error::Error::from_span(
+                                error::StaticAnalyzerErrorType::TypeMismatchReturn(
+                                    ty,
+                                    exp.node.ty(),
+                                ),
+                                &exp,
+                            ));
+                            continue;
+                        }
+
+                        Some(exp)
+                    }
+                };
+
+                ret_blocks.push(Node::from_span(
+                    parser::node::block::All::Return { value },
+                    node,
+                ));
             }
-            _ => {
-                return Err(vec![error::Error::from_cl_ln(
-                    error::StaticAnalyzerErrorType::FeatureNotImplemented("".to_string()),
-                    &node.cl_ln(),
-                )]);
+            // The rest of the block is still checked. The tree can't be compiled, so it's an
+            // error here too, even though the parser has already reported what's wrong.
+            parser::node::block::All::Error {} => {
+                errors.push(error::Error::from_span(
+                    error::StaticAnalyzerErrorType::SyntaxError,
+                    node,
+                ));
             }
         }
     }
 
-    if errors.len() > 0 {
+    if !errors.is_empty() {
         return Err(errors);
     }
 
     Ok(ret_blocks)
 }
+
+/// Evaluates the value of a variable or constant declaration and checks that it has the declared
+/// type. If no type is declared, the type is inferred from the value. Returns the type of the
+/// variable together with the typed value.
+pub fn check_declaration(
+    ty: Option<&common::Type>,
+    value: &Node<parser::node::expression::All>,
+    scope: &static_analyzer::Scope,
+) -> Result<
+    (common::Type, Node<parser::node::expression::AllWithType>),
+    Vec<error::Error<error::StaticAnalyzerErrorType>>,
+> {
+    let value = match ty {
+        Some(ty) => expression::evaluate_expecting(value, scope, ty)?,
+        None => expression::evaluate(value, scope)?,
+    };
+    let value_ty = value.node.ty();
+
+    match ty {
+        Some(ty) if *ty != value_ty => Err(vec![error::Error::from_span(
+            error::StaticAnalyzerErrorType::TypeMismatchAssign(ty.clone(), value_ty),
+            &value,
+        )]),
+        None if matches!(value_ty, common::Type::Empty | common::Type::Never) => {
+            Err(vec![error::Error::from_span(
+                error::StaticAnalyzerErrorType::InvalidVariableType(value_ty),
+                &value,
+            )])
+        }
+        _ => Ok((value_ty, value)),
+    }
+}
+
+/// Creates the operation a compound assignment does, e.g. `a + 1` for `a += 1`
+fn compound_value(
+    place: &Node<parser::node::expression::All>,
+    operator: &lexer::TokenType,
+    value: &Node<parser::node::expression::All>,
+    span: span::Span,
+) -> Node<parser::node::expression::All> {
+    use parser::node::expression::Arithmetic;
+
+    let left = Box::new(place.clone());
+    let right = Box::new(value.clone());
+
+    let value = match operator {
+        lexer::TokenType::Add => Arithmetic::Add { left, right },
+        lexer::TokenType::Sub => Arithmetic::Sub { left, right },
+        lexer::TokenType::Mul => Arithmetic::Mul { left, right },
+        lexer::TokenType::Div => Arithmetic::Div { left, right },
+        lexer::TokenType::Mod => Arithmetic::Mod { left, right },
+        lexer::TokenType::Pow => Arithmetic::Pow { left, right },
+        _ => unreachable!("The parser only gives arithmetic operators to compound assignments"),
+    };
+
+    Node::from_span(parser::node::expression::All::Arithmetic { value }, &span)
+}
+
+/// Returns the variable that is assigned to by an assignment, e.g. `a` for `a.b[0].c = 3`
+///
+/// The parser makes sure that the target of an assignment is a variable or a property of one
+fn assigned_variable(target: &Node<parser::node::expression::All>) -> (&[String], &str) {
+    use parser::node::expression;
+
+    match &target.node {
+        expression::All::SingleDataUnit {
+            value:
+                expression::SingleDataUnit::Identifier {
+                    namespace,
+                    identifier,
+                },
+        } => (namespace, identifier),
+        expression::All::SingleDataUnit {
+            value: expression::SingleDataUnit::PropertyAccess { object, .. },
+        } => assigned_variable(object),
+        expression::All::SingleDataUnit {
+            value: expression::SingleDataUnit::ArrayAccess { array, .. },
+        } => assigned_variable(array),
+        _ => {
+            unreachable!("The parser only allows variables, properties and elements to be assigned")
+        }
+    }
+}
+
+/// Checks a nested block, e.g. the body of an if statement, in its own scope
+fn check_child(
+    tree: &parser::node::Block<parser::node::expression::All>,
+    scope: &static_analyzer::Scope,
+) -> Result<
+    parser::node::Block<parser::node::expression::AllWithType>,
+    Vec<error::Error<error::StaticAnalyzerErrorType>>,
+> {
+    let mut child_scope = static_analyzer::Scope::new(Some(scope));
+
+    check(tree, &mut child_scope)
+}
+
+/// Checks an if statement together with all of its `else if` branches, which are checked in a
+/// loop
+fn check_if(
+    node: &parser::node::BlockNode<parser::node::expression::All>,
+    scope: &static_analyzer::Scope,
+) -> Result<
+    parser::node::BlockNode<parser::node::expression::AllWithType>,
+    Vec<error::Error<error::StaticAnalyzerErrorType>>,
+> {
+    let mut errors = vec![];
+    let mut branches = vec![];
+    let mut branch = node;
+
+    let else_body = loop {
+        let (cond, then_body, else_body) = match &branch.node {
+            parser::node::block::All::If { cond, then_body } => (cond, then_body, None),
+            parser::node::block::All::IfElse {
+                cond,
+                then_body,
+                else_body,
+            } => (cond, then_body, Some(else_body)),
+            _ => unreachable!("Only if statements are checked as if statements"),
+        };
+
+        match (check_cond(cond, scope), check_child(then_body, scope)) {
+            (Ok(cond), Ok(then_body)) => branches.push((branch, cond, then_body)),
+            (cond, then_body) => {
+                errors.extend(cond.err().unwrap_or_default());
+                errors.extend(then_body.err().unwrap_or_default());
+            }
+        }
+
+        match else_body {
+            Some(else_body) => match parser::node::block::else_if(else_body) {
+                Some(else_if) => branch = else_if,
+                None => break Some(check_child(else_body, scope)),
+            },
+            None => break None,
+        }
+    };
+
+    let mut else_body = match else_body {
+        Some(Ok(else_body)) => Some(else_body),
+        Some(Err(errs)) => {
+            errors.extend(errs);
+            None
+        }
+        None => None,
+    };
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // The branches are put back together from the last one, the same way the parser does
+    let mut statement = None;
+
+    for (branch, cond, then_body) in branches.into_iter().rev() {
+        let node = match statement
+            .take()
+            .map(|else_if| vec![else_if])
+            .or(else_body.take())
+        {
+            Some(else_body) => parser::node::block::All::IfElse {
+                cond,
+                then_body,
+                else_body,
+            },
+            None => parser::node::block::All::If { cond, then_body },
+        };
+
+        statement = Some(Node::from_span(node, branch));
+    }
+
+    Ok(statement.expect("An if statement has at least one branch"))
+}
+
+/// Evaluates a condition and makes sure that it's a boolean
+fn check_cond(
+    cond: &Node<parser::node::expression::All>,
+    scope: &static_analyzer::Scope,
+) -> Result<
+    Node<parser::node::expression::AllWithType>,
+    Vec<error::Error<error::StaticAnalyzerErrorType>>,
+> {
+    let cond = expression::evaluate(cond, scope)?;
+
+    if cond.node.ty() != common::Type::Boolean {
+        return Err(vec![error::Error::from_span(
+            error::StaticAnalyzerErrorType::TypeMismatchCondition(cond.node.ty()),
+            &cond,
+        )]);
+    }
+
+    Ok(cond)
+}
+
+/// Calculates what type a block evaluates to when it's run. `Type::Never` means that the code
+/// after the block can never be reached, e.g. because the block always returns.
+///
+/// # Example
+/// ```text
+/// {
+///     var a: int = 3;
+/// } // Empty
+///
+/// {
+///     if a == 3 {
+///         ret 1;
+///     } else {
+///         ret 2;
+///     }
+/// } // Never
+/// ```
+pub fn flow_type(
+    tree: &parser::node::Block<parser::node::expression::AllWithType>,
+) -> common::Type {
+    let diverges = tree.iter().any(|node| match &node.node {
+        parser::node::block::All::Return { .. }
+        | parser::node::block::All::Break {}
+        | parser::node::block::All::Continue {} => true,
+        parser::node::block::All::Expression { value } => value.node.ty() == common::Type::Never,
+        // Diverges if every branch does, an `if` without an `else` never does
+        parser::node::block::All::IfElse { .. } => {
+            let mut branch = node;
+
+            loop {
+                let parser::node::block::All::IfElse {
+                    then_body,
+                    else_body,
+                    ..
+                } = &branch.node
+                else {
+                    break false;
+                };
+
+                if flow_type(then_body) != common::Type::Never {
+                    break false;
+                }
+
+                match parser::node::block::else_if(else_body) {
+                    Some(else_if) => branch = else_if,
+                    None => break flow_type(else_body) == common::Type::Never,
+                }
+            }
+        }
+        _ => false,
+    });
+
+    if diverges {
+        common::Type::Never
+    } else {
+        common::Type::Empty
+    }
+}
@@ -0,0 +1,27 @@
+//! Functions that are built into the compiler instead of being declared in zyrahn, e.g. because
+//! they take an array of any type, which can't be expressed in a function declaration.
+//!
+//! # Examples
+//! ```text
+//! std::len([1, 2, 3]) // 3
+//! ```
+use crate::*;
+
+/// Returns the return type of the built-in function with the given absolute name and arguments
+pub fn get_function(
+    ns: &[String],
+    name: &str,
+    args: &[(bool, common::Type)],
+) -> Option<common::Type> {
+    match (Scope::combine_ns_name(ns, name).as_str(), args) {
+        ("std::len", [(false, common::Type::Array(_))]) => Some(common::Type::Integer),
+        _ => None,
+    }
+}
+
+/// Returns if there is a built-in function with the given absolute name, no matter the arguments
+pub fn has_function(ns: &[String], name: &str) -> bool {
+    matches!(Scope::combine_ns_name(ns, name).as_str(), "std::len")
+}
+
+use super::Scope;
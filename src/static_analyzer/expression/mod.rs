@@ -1,23 +1,27 @@
 use crate::*;
-use cl_ln::ClLn;
 use parser::node::*;
-
-/// Calculates what type both expressions have and return errors if they don't match
+use std::collections::HashMap;
+
+/// The evaluated left and right operand of an operator together with the type they both have
+type Operands = (
+    Node<expression::AllWithType>,
+    Node<expression::AllWithType>,
+    common::Type,
+);
+
+/// Evaluates the right operand and calculates what type both operands have, returns errors if they
+/// don't match. The left operand has already been evaluated by `evaluate`. The evaluated operands
+/// are returned so that each of them only has to be evaluated once.
 fn calc_type(
-    left: &Node<expression::All>,
+    left: Result<Node<expression::AllWithType>, Vec<error::Error<error::StaticAnalyzerErrorType>>>,
     right: &Node<expression::All>,
     scope: &static_analyzer::Scope,
     op: lexer::TokenType,
-) -> Result<common::Type, Vec<error::Error<error::StaticAnalyzerErrorType>>> {
-    let left = evaluate(left, scope);
+) -> Result<Operands, Vec<error::Error<error::StaticAnalyzerErrorType>>> {
     let right = evaluate(right, scope);
 
-    if left.is_err() && right.is_err() {
-        return Err(left
-            .unwrap_err()
-            .into_iter()
-            .chain(right.unwrap_err())
-            .collect());
+    if let (Err(left_errs), Err(right_errs)) = (&left, &right) {
+        return Err(left_errs.iter().chain(right_errs).cloned().collect());
     }
 
     let left = left?;
@@ -27,22 +31,158 @@ fn calc_type(
     let right_ty = right.node.ty();
 
     if left_ty == right_ty {
-        Ok(left_ty)
+        Ok((left, right, left_ty))
     } else {
-        Err(vec![error::Error::from_cl_ln(
+        Err(vec![error::Error::from_span(
             error::StaticAnalyzerErrorType::TypeMismatchOp(op, left_ty, right_ty),
-            &cl_ln::combine(&[left, right]),
+            &span::combine(&[left, right]),
         )])
     }
 }
 
+/// Same as `evaluate`, but used when the type the expression should have is already known, e.g.
+/// in a variable declaration. This makes it possible to use an empty array, since its type can't
+/// be inferred from its elements.
+pub fn evaluate_expecting(
+    node: &Node<expression::All>,
+    scope: &static_analyzer::Scope,
+    expected: &common::Type,
+) -> Result<Node<expression::AllWithType>, Vec<error::Error<error::StaticAnalyzerErrorType>>> {
+    match (&node.node, expected) {
+        (
+            expression::All::SingleDataUnit {
+                value: expression::SingleDataUnit::ArrayInit { values },
+            },
+            common::Type::Array(element_ty),
+        ) => array_init(node, values, scope, Some(element_ty)),
+        _ => evaluate(node, scope),
+    }
+}
+
+/// Evaluates an array init. The type of the elements is decided by the first element, unless it's
+/// already known.
+fn array_init(
+    node: &Node<expression::All>,
+    values: &[Node<expression::All>],
+    scope: &static_analyzer::Scope,
+    element_ty: Option<&common::Type>,
+) -> Result<Node<expression::AllWithType>, Vec<error::Error<error::StaticAnalyzerErrorType>>> {
+    let mut errors = vec![];
+    let mut typed_values = vec![];
+    let mut element_ty = element_ty.cloned();
+
+    for value in values {
+        let value = match &element_ty {
+            Some(ty) => evaluate_expecting(value, scope, ty),
+            None => evaluate(value, scope),
+        };
+
+        match value {
+            Ok(value) => {
+                match &element_ty {
+                    Some(ty) if value.node.ty() != *ty => {
+                        errors.push(error::Error::from_span(
+                            error::StaticAnalyzerErrorType::TypeMismatchArrayElement(
+                                ty.clone(),
+                                value.node.ty(),
+                            ),
+                            &value,
+                        ));
+                    }
+                    Some(_) => {}
+                    None => element_ty = Some(value.node.ty()),
+                }
+
+                typed_values.push(value);
+            }
+            Err(errs) => errors.extend(errs),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let Some(element_ty) = element_ty else {
+        return Err(vec![error::Error::from_span(
+            error::StaticAnalyzerErrorType::CannotInferEmptyArray,
+            node,
+        )]);
+    };
+
+    Ok(Node::from_span(
+        expression::AllWithType::SingleDataUnit {
+            value: expression::SingleDataUnit::ArrayInit {
+                values: typed_values,
+            },
+            ty: common::Type::Array(Box::new(element_ty)),
+        },
+        node,
+    ))
+}
+
 pub fn evaluate(
     node: &Node<expression::All>,
     scope: &static_analyzer::Scope,
+) -> Result<Node<expression::AllWithType>, Vec<error::Error<error::StaticAnalyzerErrorType>>> {
+    // Binary operators nest to the left, e.g. `a + b + c` is `(a + b) + c`, so a long chain of
+    // them is walked in a loop instead of recursing into every left operand
+    let mut chain = vec![];
+    let mut leftmost = node;
+    while let Some(left) = left_operand(leftmost) {
+        chain.push(leftmost);
+        leftmost = left;
+    }
+
+    let mut value = evaluate_node(leftmost, None, scope);
+    for node in chain.into_iter().rev() {
+        value = evaluate_node(node, Some(value), scope);
+    }
+
+    value
+}
+
+/// Returns the left operand if the node is a binary operator
+fn left_operand(node: &Node<expression::All>) -> Option<&Node<expression::All>> {
+    match &node.node {
+        expression::All::Arithmetic { value } => match value {
+            expression::Arithmetic::Add { left, .. }
+            | expression::Arithmetic::Sub { left, .. }
+            | expression::Arithmetic::Mul { left, .. }
+            | expression::Arithmetic::Div { left, .. }
+            | expression::Arithmetic::Mod { left, .. }
+            | expression::Arithmetic::Pow { left, .. } => Some(left),
+            expression::Arithmetic::Neg { .. } => None,
+        },
+        expression::All::BooleanLogic { value } => match value {
+            expression::BooleanLogic::And { left, .. }
+            | expression::BooleanLogic::Or { left, .. } => Some(left),
+            expression::BooleanLogic::Not { .. } => None,
+        },
+        expression::All::Cmp { value } => match value {
+            expression::Cmp::Equal { left, .. }
+            | expression::Cmp::NotEqual { left, .. }
+            | expression::Cmp::LessThan { left, .. }
+            | expression::Cmp::LessThanOrEqual { left, .. }
+            | expression::Cmp::GreaterThan { left, .. }
+            | expression::Cmp::GreaterThanOrEqual { left, .. } => Some(left),
+        },
+        expression::All::SingleDataUnit { .. } => None,
+    }
+}
+
+/// Evaluates a single node. The left operand of a binary operator is passed in already evaluated,
+/// see `evaluate`.
+fn evaluate_node(
+    node: &Node<expression::All>,
+    evaluated_left: Option<
+        Result<Node<expression::AllWithType>, Vec<error::Error<error::StaticAnalyzerErrorType>>>,
+    >,
+    scope: &static_analyzer::Scope,
 ) -> Result<Node<expression::AllWithType>, Vec<error::Error<error::StaticAnalyzerErrorType>>> {
     macro_rules! with_type {
         ($scope:ident::$name:ident, $l:expr, $r:expr, $ty:expr) => {{
-            Node::from_cl_ln(
+            Node::from_span(
                 expression::AllWithType::$scope {
                     value: expression::$scope::$name {
                         left: Box::new($l),
@@ -60,7 +200,7 @@ pub fn evaluate(
             if $allowed_ty.contains(&$ty) {
                 $if_allowed
             } else {
-                Err(vec![error::Error::from_cl_ln(
+                Err(vec![error::Error::from_span(
                     error::StaticAnalyzerErrorType::OperationNotSupported(
                         lexer::TokenType::$opt,
                         $ty,
@@ -72,15 +212,9 @@ pub fn evaluate(
     }
 
     match &node.node {
-        expression::All::CompilerCustomCodePreDefined { .. } => {
-            return Err(vec![error::Error::from_cl_ln(
-                error::StaticAnalyzerErrorType::CompilerCustomCodePreDefined,
-                node,
-            )])
-        }
         expression::All::SingleDataUnit { value, .. } => match value {
             expression::SingleDataUnit::Literal { literal, .. } => match literal {
-                expression::Literal::Integer { value } => Ok(Node::from_cl_ln(
+                expression::Literal::Integer { value } => Ok(Node::from_span(
                     expression::AllWithType::SingleDataUnit {
                         value: expression::SingleDataUnit::Literal {
                             literal: expression::Literal::Integer { value: *value },
@@ -89,7 +223,7 @@ pub fn evaluate(
                     },
                     node,
                 )),
-                expression::Literal::Float { value } => Ok(Node::from_cl_ln(
+                expression::Literal::Float { value } => Ok(Node::from_span(
                     expression::AllWithType::SingleDataUnit {
                         value: expression::SingleDataUnit::Literal {
                             literal: expression::Literal::Float { value: *value },
@@ -98,7 +232,7 @@ pub fn evaluate(
                     },
                     node,
                 )),
-                expression::Literal::String { value } => Ok(Node::from_cl_ln(
+                expression::Literal::String { value } => Ok(Node::from_span(
                     expression::AllWithType::SingleDataUnit {
                         value: expression::SingleDataUnit::Literal {
                             literal: expression::Literal::String {
@@ -109,7 +243,7 @@ pub fn evaluate(
                     },
                     node,
                 )),
-                expression::Literal::Boolean { value } => Ok(Node::from_cl_ln(
+                expression::Literal::Boolean { value } => Ok(Node::from_span(
                     expression::AllWithType::SingleDataUnit {
                         value: expression::SingleDataUnit::Literal {
                             literal: expression::Literal::Boolean { value: *value },
@@ -125,127 +259,132 @@ pub fn evaluate(
                 ..
             } => match function.as_ref() {
                 Node {
-                    node: expression::All::SingleDataUnit { value, .. },
+                    node:
+                        expression::All::SingleDataUnit {
+                            value:
+                                expression::SingleDataUnit::Identifier {
+                                    namespace,
+                                    identifier,
+                                },
+                            ..
+                        },
                     ..
-                } => match value {
-                    expression::SingleDataUnit::Identifier {
-                        namespace,
-                        identifier,
-                    } => {
-                        let args = arguments
-                            .iter()
-                            .map(|(is_out, arg)| {
-                                let arg = evaluate(arg, scope);
-
-                                if arg.is_err() {
-                                    return Err(arg.unwrap_err());
-                                } else {
-                                    Ok((is_out.clone(), arg.unwrap()))
-                                }
-                            })
-                            .collect::<Result<Vec<(bool, Node<expression::AllWithType>)>, _>>()?;
-
-                        let args_types = args
-                            .iter()
-                            .map(|(is_out, arg)| (*is_out, arg.node.ty()))
-                            .collect();
-
-                        for (is_out, arg) in args.iter() {
-                            if *is_out {
-                                match arg {
+                } => {
+                    let args = arguments
+                        .iter()
+                        .map(|(is_out, arg)| evaluate(arg, scope).map(|arg| (*is_out, arg)))
+                        .collect::<Result<Vec<(bool, Node<expression::AllWithType>)>, _>>()?;
+
+                    let args_types = args
+                        .iter()
+                        .map(|(is_out, arg)| (*is_out, arg.node.ty()))
+                        .collect::<Vec<_>>();
+
+                    for (is_out, arg) in args.iter() {
+                        if *is_out {
+                            match arg {
                                     Node {
                                         node:
                                             expression::AllWithType::SingleDataUnit {
-                                                value: expression::SingleDataUnit::Identifier { .. },
+                                                value: expression::SingleDataUnit::Identifier { identifier, namespace },
                                                 ..
                                             },
                                         ..
-                                    } => {}
+                                    } => {
+                                        if scope
+                                            .resolve_variable(namespace, identifier)
+                                            .is_some_and(|(_, variable)| !variable.mutable)
+                                        {
+                                            return Err(vec![error::Error::from_span(
+                                                error::StaticAnalyzerErrorType::CannotUseConstantAsOutArgument(
+                                                    identifier.clone(),
+                                                ),
+                                                arg,
+                                            )]);
+                                        }
+                                    }
                                     _ => {
-                                        return Err(vec![error::Error::from_cl_ln(
+                                        return Err(vec![error::Error::from_span(
                                             error::StaticAnalyzerErrorType::CannotUseNonIdentifierAsOutArgument,
                                             arg,
                                         )])
                                     }
                                 }
-                            }
                         }
+                    }
 
-                        if let Some(ret_type) =
-                            scope.get_function(namespace, identifier, &args_types)
-                        {
-                            Ok(Node::from_cl_ln(
-                                expression::AllWithType::SingleDataUnit {
-                                    value: expression::SingleDataUnit::FunctionCall {
-                                        function: Box::new(Node::from_cl_ln(
-                                            expression::AllWithType::SingleDataUnit {
-                                                value: expression::SingleDataUnit::Identifier {
-                                                    namespace: namespace.clone(),
-                                                    identifier: identifier.clone(),
-                                                },
-                                                ty: common::Type::Never,
+                    if let Some((full_namespace, ret_type)) =
+                        scope.resolve_function(namespace, identifier, &args_types)
+                    {
+                        Ok(Node::from_span(
+                            expression::AllWithType::SingleDataUnit {
+                                value: expression::SingleDataUnit::FunctionCall {
+                                    function: Box::new(Node::from_span(
+                                        expression::AllWithType::SingleDataUnit {
+                                            value: expression::SingleDataUnit::Identifier {
+                                                namespace: full_namespace,
+                                                identifier: identifier.clone(),
                                             },
-                                            node,
-                                        )),
-                                        arguments: args,
-                                    },
-                                    ty: ret_type.clone(),
+                                            ty: common::Type::Never,
+                                        },
+                                        node,
+                                    )),
+                                    arguments: args,
                                 },
-                                node,
-                            ))
-                        } else {
-                            if scope.has_function(namespace, identifier) {
-                                return Err(vec![error::Error::from_cl_ln(
-                                    error::StaticAnalyzerErrorType::FunctionArgumentMismatch(
-                                        identifier.clone(),
-                                        namespace.clone(),
-                                        args_types,
-                                    ),
-                                    node,
-                                )]);
-                            }
-
-                            Err(vec![error::Error::from_cl_ln(
-                                error::StaticAnalyzerErrorType::FunctionNotDefined(
+                                ty: ret_type,
+                            },
+                            node,
+                        ))
+                    } else {
+                        if scope
+                            .lookup_namespaces(namespace)
+                            .iter()
+                            .any(|full_ns| scope.has_function(full_ns, identifier))
+                        {
+                            return Err(vec![error::Error::from_span(
+                                error::StaticAnalyzerErrorType::FunctionArgumentMismatch(
                                     identifier.clone(),
                                     namespace.clone(),
+                                    args_types,
                                 ),
                                 node,
-                            )])
+                            )]);
                         }
-                    }
-                    _ => {
-                        return Err(vec![error::Error::from_cl_ln(
-                            error::StaticAnalyzerErrorType::CannotCallNonFunction,
+
+                        Err(vec![error::Error::from_span(
+                            error::StaticAnalyzerErrorType::FunctionNotDefined(
+                                identifier.clone(),
+                                namespace.clone(),
+                            ),
                             node,
                         )])
                     }
-                },
-                _ => {
-                    return Err(vec![error::Error::from_cl_ln(
-                        error::StaticAnalyzerErrorType::CannotCallNonFunction,
-                        node,
-                    )])
                 }
+                _ => Err(vec![error::Error::from_span(
+                    error::StaticAnalyzerErrorType::CannotCallNonFunction,
+                    node,
+                )]),
             },
             expression::SingleDataUnit::Identifier {
                 identifier,
                 namespace,
                 ..
             } => {
-                if let Some(value) = scope.get_variable(&namespace, &identifier) {
-                    Ok(Node::from_cl_ln(
+                if let Some((full_namespace, variable)) =
+                    scope.resolve_variable(namespace, identifier)
+                {
+                    Ok(Node::from_span(
                         expression::AllWithType::SingleDataUnit {
                             value: expression::SingleDataUnit::Identifier {
                                 identifier: identifier.clone(),
-                                namespace: namespace.clone(),
+                                namespace: full_namespace,
                             },
-                            ty: value.clone(),
+                            ty: variable.ty.clone(),
                         },
                         node,
                     ))
                 } else {
-                    Err(vec![error::Error::from_cl_ln(
+                    Err(vec![error::Error::from_span(
                         error::StaticAnalyzerErrorType::VariableNotDefined(
                             identifier.clone(),
                             namespace.clone(),
@@ -254,166 +393,346 @@ pub fn evaluate(
                     )])
                 }
             }
-            expression::SingleDataUnit::ArrayInit { .. } => {
-                return Err(vec![error::Error::from_cl_ln(
-                    error::StaticAnalyzerErrorType::FeatureNotImplemented("Array init".to_string()),
-                    node,
-                )])
+            expression::SingleDataUnit::ArrayInit { values } => {
+                array_init(node, values, scope, None)
             }
-            expression::SingleDataUnit::StructInit { .. } => {
-                return Err(vec![error::Error::from_cl_ln(
-                    error::StaticAnalyzerErrorType::FeatureNotImplemented(
-                        "Struct init".to_string(),
-                    ),
+            expression::SingleDataUnit::StructInit {
+                namespace,
+                identifier,
+                values,
+            } => {
+                let Some((full_namespace, fields)) = scope.resolve_struct(namespace, identifier)
+                else {
+                    return Err(vec![error::Error::from_span(
+                        error::StaticAnalyzerErrorType::TypeNotDefined(common::Type::Struct {
+                            namespace: namespace.clone(),
+                            identifier: identifier.clone(),
+                        }),
+                        node,
+                    )]);
+                };
+
+                let ty = common::Type::Struct {
+                    namespace: full_namespace.clone(),
+                    identifier: identifier.clone(),
+                };
+
+                let mut errors = vec![];
+                let mut typed_values = HashMap::new();
+
+                for (field, field_ty) in fields {
+                    let Some(value) = values.get(field) else {
+                        errors.push(error::Error::from_span(
+                            error::StaticAnalyzerErrorType::StructFieldMissing(
+                                ty.clone(),
+                                field.clone(),
+                            ),
+                            node,
+                        ));
+                        continue;
+                    };
+
+                    match evaluate_expecting(value, scope, field_ty) {
+                        Ok(value) if value.node.ty() != *field_ty => {
+                            errors.push(error::Error::from_span(
+                                error::StaticAnalyzerErrorType::TypeMismatchAssign(
+                                    field_ty.clone(),
+                                    value.node.ty(),
+                                ),
+                                &value,
+                            ));
+                        }
+                        Ok(value) => {
+                            typed_values.insert(field.clone(), value);
+                        }
+                        Err(errs) => errors.extend(errs),
+                    }
+                }
+
+                // Sorted so that the errors are reported in the same order every time
+                let mut extra_fields = values
+                    .iter()
+                    .filter(|(field, _)| !fields.iter().any(|(f, _)| f == *field))
+                    .collect::<Vec<_>>();
+                extra_fields.sort_by_key(|(field, _)| *field);
+
+                for (field, value) in extra_fields {
+                    errors.push(error::Error::from_span(
+                        error::StaticAnalyzerErrorType::StructFieldNotDefined(
+                            ty.clone(),
+                            field.clone(),
+                        ),
+                        value,
+                    ));
+                }
+
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+
+                Ok(Node::from_span(
+                    expression::AllWithType::SingleDataUnit {
+                        value: expression::SingleDataUnit::StructInit {
+                            namespace: full_namespace,
+                            identifier: identifier.clone(),
+                            values: typed_values,
+                        },
+                        ty,
+                    },
                     node,
-                )])
+                ))
             }
-            expression::SingleDataUnit::ArrayAccess { .. } => {
-                return Err(vec![error::Error::from_cl_ln(
-                    error::StaticAnalyzerErrorType::FeatureNotImplemented(
-                        "Array access".to_string(),
-                    ),
+            expression::SingleDataUnit::ArrayAccess { array, index } => {
+                let array = evaluate(array, scope);
+                let index = evaluate(index, scope);
+
+                if let (Err(array_errs), Err(index_errs)) = (&array, &index) {
+                    return Err(array_errs.iter().chain(index_errs).cloned().collect());
+                }
+
+                let array = array?;
+                let index = index?;
+
+                let common::Type::Array(element_ty) = array.node.ty() else {
+                    return Err(vec![error::Error::from_span(
+                        error::StaticAnalyzerErrorType::OperationNotSupported(
+                            lexer::TokenType::SquareOpen,
+                            array.node.ty(),
+                        ),
+                        node,
+                    )]);
+                };
+
+                if index.node.ty() != common::Type::Integer {
+                    return Err(vec![error::Error::from_span(
+                        error::StaticAnalyzerErrorType::TypeMismatchIndex(index.node.ty()),
+                        &index,
+                    )]);
+                }
+
+                Ok(Node::from_span(
+                    expression::AllWithType::SingleDataUnit {
+                        value: expression::SingleDataUnit::ArrayAccess {
+                            array: Box::new(array),
+                            index: Box::new(index),
+                        },
+                        ty: *element_ty,
+                    },
                     node,
-                )])
+                ))
             }
-            expression::SingleDataUnit::PropertyAccess { .. } => {
-                return Err(vec![error::Error::from_cl_ln(
-                    error::StaticAnalyzerErrorType::FeatureNotImplemented(
-                        "Property access".to_string(),
-                    ),
+            expression::SingleDataUnit::PropertyAccess { object, property } => {
+                let object = evaluate(object, scope)?;
+                let object_ty = object.node.ty();
+
+                let fields = match &object_ty {
+                    common::Type::Struct {
+                        namespace,
+                        identifier,
+                    } => scope.get_struct(namespace, identifier),
+                    _ => None,
+                };
+
+                let Some(fields) = fields else {
+                    return Err(vec![error::Error::from_span(
+                        error::StaticAnalyzerErrorType::OperationNotSupported(
+                            lexer::TokenType::Dot,
+                            object_ty,
+                        ),
+                        node,
+                    )]);
+                };
+
+                let Some((_, ty)) = fields.iter().find(|(field, _)| field == property) else {
+                    return Err(vec![error::Error::from_span(
+                        error::StaticAnalyzerErrorType::StructFieldNotDefined(
+                            object_ty,
+                            property.clone(),
+                        ),
+                        node,
+                    )]);
+                };
+
+                Ok(Node::from_span(
+                    expression::AllWithType::SingleDataUnit {
+                        value: expression::SingleDataUnit::PropertyAccess {
+                            object: Box::new(object),
+                            property: property.clone(),
+                        },
+                        ty: ty.clone(),
+                    },
                     node,
-                )])
+                ))
             }
         },
         expression::All::Cmp { value, .. } => match value {
             expression::Cmp::Equal { left, right, .. } => {
-                let ty = calc_type(&left, &right, scope, lexer::TokenType::Equal)?;
+                let (left, right, ty) = calc_type(
+                    evaluated_left.unwrap_or_else(|| evaluate(left, scope)),
+                    right,
+                    scope,
+                    lexer::TokenType::Equal,
+                )?;
 
                 check_type!(
                     Equal,
                     ty,
-                    vec![
+                    [
                         common::Type::Integer,
                         common::Type::Float,
                         common::Type::String,
                         common::Type::Boolean
                     ],
-                    {
-                        let left = evaluate(&left, scope)?;
-                        let right = evaluate(&right, scope)?;
-
-                        Ok(with_type!(Cmp::Equal, left, right, ty))
-                    }
+                    { Ok(with_type!(Cmp::Equal, left, right, common::Type::Boolean)) }
                 )
             }
             expression::Cmp::NotEqual { left, right, .. } => {
-                let ty = calc_type(&left, &right, scope, lexer::TokenType::NotEqual)?;
+                let (left, right, ty) = calc_type(
+                    evaluated_left.unwrap_or_else(|| evaluate(left, scope)),
+                    right,
+                    scope,
+                    lexer::TokenType::NotEqual,
+                )?;
 
                 check_type!(
                     NotEqual,
                     ty,
-                    vec![
+                    [
                         common::Type::Integer,
                         common::Type::Float,
                         common::Type::String,
                         common::Type::Boolean
                     ],
                     {
-                        let left = evaluate(&left, scope)?;
-                        let right = evaluate(&right, scope)?;
-
-                        Ok(with_type!(Cmp::NotEqual, left, right, ty))
+                        Ok(with_type!(
+                            Cmp::NotEqual,
+                            left,
+                            right,
+                            common::Type::Boolean
+                        ))
                     }
                 )
             }
             expression::Cmp::LessThan { left, right, .. } => {
-                let ty = calc_type(&left, &right, scope, lexer::TokenType::LessThan)?;
+                let (left, right, ty) = calc_type(
+                    evaluated_left.unwrap_or_else(|| evaluate(left, scope)),
+                    right,
+                    scope,
+                    lexer::TokenType::LessThan,
+                )?;
 
                 check_type!(
                     LessThan,
                     ty,
-                    vec![common::Type::Integer, common::Type::Float],
+                    [common::Type::Integer, common::Type::Float],
                     {
-                        let left = evaluate(&left, scope)?;
-                        let right = evaluate(&right, scope)?;
-
-                        Ok(with_type!(Cmp::LessThan, left, right, ty))
+                        Ok(with_type!(
+                            Cmp::LessThan,
+                            left,
+                            right,
+                            common::Type::Boolean
+                        ))
                     }
                 )
             }
             expression::Cmp::LessThanOrEqual { left, right, .. } => {
-                let ty = calc_type(&left, &right, scope, lexer::TokenType::LessThanOrEqual)?;
+                let (left, right, ty) = calc_type(
+                    evaluated_left.unwrap_or_else(|| evaluate(left, scope)),
+                    right,
+                    scope,
+                    lexer::TokenType::LessThanOrEqual,
+                )?;
 
                 check_type!(
                     LessThanOrEqual,
                     ty,
-                    vec![common::Type::Integer, common::Type::Float],
+                    [common::Type::Integer, common::Type::Float],
                     {
-                        let left = evaluate(&left, scope)?;
-                        let right = evaluate(&right, scope)?;
-
-                        Ok(with_type!(Cmp::LessThanOrEqual, left, right, ty))
+                        Ok(with_type!(
+                            Cmp::LessThanOrEqual,
+                            left,
+                            right,
+                            common::Type::Boolean
+                        ))
                     }
                 )
             }
             expression::Cmp::GreaterThan { left, right, .. } => {
-                let ty = calc_type(&left, &right, scope, lexer::TokenType::GreaterThan)?;
+                let (left, right, ty) = calc_type(
+                    evaluated_left.unwrap_or_else(|| evaluate(left, scope)),
+                    right,
+                    scope,
+                    lexer::TokenType::GreaterThan,
+                )?;
 
                 check_type!(
                     GreaterThan,
                     ty,
-                    vec![common::Type::Integer, common::Type::Float],
+                    [common::Type::Integer, common::Type::Float],
                     {
-                        let left = evaluate(&left, scope)?;
-                        let right = evaluate(&right, scope)?;
-
-                        Ok(with_type!(Cmp::GreaterThan, left, right, ty))
+                        Ok(with_type!(
+                            Cmp::GreaterThan,
+                            left,
+                            right,
+                            common::Type::Boolean
+                        ))
                     }
                 )
             }
             expression::Cmp::GreaterThanOrEqual { left, right, .. } => {
-                let ty = calc_type(&left, &right, scope, lexer::TokenType::GreaterThanOrEqual)?;
+                let (left, right, ty) = calc_type(
+                    evaluated_left.unwrap_or_else(|| evaluate(left, scope)),
+                    right,
+                    scope,
+                    lexer::TokenType::GreaterThanOrEqual,
+                )?;
 
                 check_type!(
                     GreaterThanOrEqual,
                     ty,
-                    vec![common::Type::Integer, common::Type::Float],
+                    [common::Type::Integer, common::Type::Float],
                     {
-                        let left = evaluate(&left, scope)?;
-                        let right = evaluate(&right, scope)?;
-
-                        Ok(with_type!(Cmp::GreaterThanOrEqual, left, right, ty))
+                        Ok(with_type!(
+                            Cmp::GreaterThanOrEqual,
+                            left,
+                            right,
+                            common::Type::Boolean
+                        ))
                     }
                 )
             }
         },
         expression::All::BooleanLogic { value, .. } => match value {
             expression::BooleanLogic::And { left, right, .. } => {
-                let ty = calc_type(&left, &right, scope, lexer::TokenType::And)?;
-
-                check_type!(And, ty, vec![common::Type::Boolean], {
-                    let left = evaluate(&left, scope)?;
-                    let right = evaluate(&right, scope)?;
-
+                let (left, right, ty) = calc_type(
+                    evaluated_left.unwrap_or_else(|| evaluate(left, scope)),
+                    right,
+                    scope,
+                    lexer::TokenType::And,
+                )?;
+
+                check_type!(And, ty, [common::Type::Boolean], {
                     Ok(with_type!(BooleanLogic::And, left, right, ty))
                 })
             }
             expression::BooleanLogic::Or { left, right, .. } => {
-                let ty = calc_type(&left, &right, scope, lexer::TokenType::Or)?;
-
-                check_type!(Or, ty, vec![common::Type::Boolean], {
-                    let left = evaluate(&left, scope)?;
-                    let right = evaluate(&right, scope)?;
-
+                let (left, right, ty) = calc_type(
+                    evaluated_left.unwrap_or_else(|| evaluate(left, scope)),
+                    right,
+                    scope,
+                    lexer::TokenType::Or,
+                )?;
+
+                check_type!(Or, ty, [common::Type::Boolean], {
                     Ok(with_type!(BooleanLogic::Or, left, right, ty))
                 })
             }
             expression::BooleanLogic::Not { value, .. } => {
-                let value = evaluate(&value, scope)?;
+                let value = evaluate(value, scope)?;
                 let ty = value.node.ty();
 
                 if ty == common::Type::Boolean {
-                    Ok(Node::from_cl_ln(
+                    Ok(Node::from_span(
                         expression::AllWithType::BooleanLogic {
                             value: expression::BooleanLogic::Not {
                                 value: Box::new(value),
@@ -423,7 +742,7 @@ pub fn evaluate(
                         node,
                     ))
                 } else {
-                    Err(vec![error::Error::from_cl_ln(
+                    Err(vec![error::Error::from_span(
                         error::StaticAnalyzerErrorType::OperationNotSupportedNot(ty),
                         node,
                     )])
@@ -432,80 +751,90 @@ pub fn evaluate(
         },
         expression::All::Arithmetic { value, .. } => match value {
             expression::Arithmetic::Add { left, right, .. } => {
-                let ty = calc_type(&left, &right, scope, lexer::TokenType::Add)?;
+                let (left, right, ty) = calc_type(
+                    evaluated_left.unwrap_or_else(|| evaluate(left, scope)),
+                    right,
+                    scope,
+                    lexer::TokenType::Add,
+                )?;
 
                 check_type!(
                     Add,
                     ty,
-                    vec![
+                    [
                         common::Type::Integer,
                         common::Type::Float,
                         common::Type::String
                     ],
-                    {
-                        let left = evaluate(&left, scope)?;
-                        let right = evaluate(&right, scope)?;
-
-                        Ok(with_type!(Arithmetic::Add, left, right, ty))
-                    }
+                    { Ok(with_type!(Arithmetic::Add, left, right, ty)) }
                 )
             }
             expression::Arithmetic::Sub { left, right, .. } => {
-                let ty = calc_type(&left, &right, scope, lexer::TokenType::Sub)?;
-
-                check_type!(Sub, ty, vec![common::Type::Integer, common::Type::Float], {
-                    let left = evaluate(&left, scope)?;
-                    let right = evaluate(&right, scope)?;
-
+                let (left, right, ty) = calc_type(
+                    evaluated_left.unwrap_or_else(|| evaluate(left, scope)),
+                    right,
+                    scope,
+                    lexer::TokenType::Sub,
+                )?;
+
+                check_type!(Sub, ty, [common::Type::Integer, common::Type::Float], {
                     Ok(with_type!(Arithmetic::Sub, left, right, ty))
                 })
             }
             expression::Arithmetic::Mul { left, right, .. } => {
-                let ty = calc_type(&left, &right, scope, lexer::TokenType::Mul)?;
-
-                check_type!(Mul, ty, vec![common::Type::Integer, common::Type::Float], {
-                    let left = evaluate(&left, scope)?;
-                    let right = evaluate(&right, scope)?;
-
+                let (left, right, ty) = calc_type(
+                    evaluated_left.unwrap_or_else(|| evaluate(left, scope)),
+                    right,
+                    scope,
+                    lexer::TokenType::Mul,
+                )?;
+
+                check_type!(Mul, ty, [common::Type::Integer, common::Type::Float], {
                     Ok(with_type!(Arithmetic::Mul, left, right, ty))
                 })
             }
             expression::Arithmetic::Div { left, right, .. } => {
-                let ty = calc_type(&left, &right, scope, lexer::TokenType::Div)?;
-
-                check_type!(Div, ty, vec![common::Type::Integer, common::Type::Float], {
-                    let left = evaluate(&left, scope)?;
-                    let right = evaluate(&right, scope)?;
-
+                let (left, right, ty) = calc_type(
+                    evaluated_left.unwrap_or_else(|| evaluate(left, scope)),
+                    right,
+                    scope,
+                    lexer::TokenType::Div,
+                )?;
+
+                check_type!(Div, ty, [common::Type::Integer, common::Type::Float], {
                     Ok(with_type!(Arithmetic::Div, left, right, ty))
                 })
             }
             expression::Arithmetic::Mod { left, right, .. } => {
-                let ty = calc_type(&left, &right, scope, lexer::TokenType::Mod)?;
-
-                check_type!(Mod, ty, vec![common::Type::Integer, common::Type::Float], {
-                    let left = evaluate(&left, scope)?;
-                    let right = evaluate(&right, scope)?;
-
+                let (left, right, ty) = calc_type(
+                    evaluated_left.unwrap_or_else(|| evaluate(left, scope)),
+                    right,
+                    scope,
+                    lexer::TokenType::Mod,
+                )?;
+
+                check_type!(Mod, ty, [common::Type::Integer, common::Type::Float], {
                     Ok(with_type!(Arithmetic::Mod, left, right, ty))
                 })
             }
             expression::Arithmetic::Pow { left, right, .. } => {
-                let ty = calc_type(&left, &right, scope, lexer::TokenType::Pow)?;
-
-                check_type!(Pow, ty, vec![common::Type::Integer, common::Type::Float], {
-                    let left = evaluate(&left, scope)?;
-                    let right = evaluate(&right, scope)?;
-
+                let (left, right, ty) = calc_type(
+                    evaluated_left.unwrap_or_else(|| evaluate(left, scope)),
+                    right,
+                    scope,
+                    lexer::TokenType::Pow,
+                )?;
+
+                check_type!(Pow, ty, [common::Type::Integer, common::Type::Float], {
                     Ok(with_type!(Arithmetic::Pow, left, right, ty))
                 })
             }
             expression::Arithmetic::Neg { value, .. } => {
-                let value = evaluate(&value, scope)?;
+                let value = evaluate(value, scope)?;
                 let ty = value.node.ty();
 
                 if ty == common::Type::Integer || ty == common::Type::Float {
-                    Ok(Node::from_cl_ln(
+                    Ok(Node::from_span(
                         expression::AllWithType::Arithmetic {
                             value: expression::Arithmetic::Neg {
                                 value: Box::new(value),
@@ -515,7 +844,7 @@ pub fn evaluate(
                         node,
                     ))
                 } else {
-                    Err(vec![error::Error::from_cl_ln(
+                    Err(vec![error::Error::from_span(
                         error::StaticAnalyzerErrorType::OperationNotSupportedNeg(ty),
                         node,
                     )])
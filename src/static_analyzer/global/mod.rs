@@ -0,0 +1,731 @@
+use super::*;
+use std::collections::HashSet;
+
+pub fn check(
+    tree: &parser::node::Global<parser::node::expression::All>,
+    scope: &mut static_analyzer::Scope,
+) -> Result<
+    parser::node::Global<parser::node::expression::AllWithType>,
+    Vec<error::Error<error::StaticAnalyzerErrorType>>,
+> {
+    let mut errors = vec![];
+
+    // All structs, functions and constants are registered before any body is checked, so that
+    // they can be used before they're declared. Structs are registered first since the types of
+    // everything else can refer to them.
+    register_structs(tree, scope, &[], &mut errors);
+    register(tree, scope, &[], &mut HashSet::new(), &mut errors);
+    register_inferred_constants(tree, scope, &mut errors);
+
+    let ret_globals = check_bodies(tree, scope, &[], &mut errors);
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(ret_globals)
+}
+
+/// Registers the names of all structs in the tree under their absolute name. The types of the
+/// fields are resolved by `register` once every struct is known.
+fn register_structs(
+    tree: &parser::node::Global<parser::node::expression::All>,
+    scope: &mut static_analyzer::Scope,
+    namespace: &[String],
+    errors: &mut Vec<error::Error<error::StaticAnalyzerErrorType>>,
+) {
+    for node in tree {
+        match &node.node {
+            parser::node::global::All::StructDeclaration {
+                identifier, fields, ..
+            } => {
+                let name = static_analyzer::Scope::combine_ns_name(namespace, identifier);
+
+                if scope.get_struct(&[], &name).is_some() {
+                    errors.push(error::Error::from_span(
+                        error::StaticAnalyzerErrorType::StructAlreadyDeclared(
+                            identifier.clone(),
+                            namespace.to_vec(),
+                        ),
+                        node,
+                    ));
+                    continue;
+                }
+
+                scope.set_struct(
+                    &name,
+                    fields
+                        .iter()
+                        .map(|field| (field.identifier.clone(), field.ty.clone()))
+                        .collect(),
+                );
+            }
+            parser::node::global::All::Namespace {
+                identifier, body, ..
+            } => {
+                let mut namespace = namespace.to_vec();
+                namespace.push(identifier.clone());
+
+                register_structs(body, scope, &namespace, errors);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Registers all functions and constants in the tree, including the ones in nested namespaces,
+/// under their absolute name. `structs` has the absolute names of the structs whose fields have
+/// been resolved, so that only the first declaration of a struct is used.
+fn register(
+    tree: &parser::node::Global<parser::node::expression::All>,
+    scope: &mut static_analyzer::Scope,
+    namespace: &[String],
+    structs: &mut HashSet<String>,
+    errors: &mut Vec<error::Error<error::StaticAnalyzerErrorType>>,
+) {
+    for node in tree {
+        match &node.node {
+            parser::node::global::All::FunctionDeclaration {
+                identifier,
+                arguments,
+                return_type,
+                ..
+            }
+            | parser::node::global::All::IntrinsicDeclaration {
+                identifier,
+                arguments,
+                return_type,
+                ..
+            }
+            | parser::node::global::All::ExternDeclaration {
+                identifier,
+                arguments,
+                return_type,
+                ..
+            } => {
+                let types = std::iter::once(return_type)
+                    .chain(arguments.iter().map(|arg| &arg.ty))
+                    .map(|ty| resolve_type(ty, scope, namespace, node, errors))
+                    .collect::<Option<Vec<_>>>();
+
+                let Some(types) = types else {
+                    continue;
+                };
+
+                let return_type = types[0].clone();
+                let args = arguments
+                    .iter()
+                    .zip(types.into_iter().skip(1))
+                    .map(|(arg, ty)| (arg.is_out, ty))
+                    .collect::<Vec<_>>();
+
+                if scope.get_function(namespace, identifier, &args).is_some() {
+                    errors.push(error::Error::from_span(
+                        error::StaticAnalyzerErrorType::FunctionAlreadyDeclared(
+                            identifier.clone(),
+                            namespace.to_vec(),
+                            args,
+                        ),
+                        node,
+                    ));
+                    continue;
+                }
+
+                scope.set_function(
+                    &static_analyzer::Scope::combine_ns_name(namespace, identifier),
+                    args,
+                    return_type,
+                );
+            }
+            parser::node::global::All::ConstantDeclaration {
+                identifier,
+                ty: Some(ty),
+                ..
+            } => {
+                let name = static_analyzer::Scope::combine_ns_name(namespace, identifier);
+
+                if scope.has_variable(&name) {
+                    errors.push(error::Error::from_span(
+                        error::StaticAnalyzerErrorType::VariableAlreadyDeclared(identifier.clone()),
+                        node,
+                    ));
+                    continue;
+                }
+
+                if let Some(ty) = resolve_type(ty, scope, namespace, node, errors) {
+                    scope.set_variable(&name, ty, false);
+                }
+            }
+            parser::node::global::All::StructDeclaration {
+                identifier, fields, ..
+            } => {
+                let name = static_analyzer::Scope::combine_ns_name(namespace, identifier);
+
+                // Duplicates have already been reported by `register_structs`
+                if !structs.insert(name.clone()) {
+                    continue;
+                }
+
+                let fields = fields
+                    .iter()
+                    .map(|field| {
+                        resolve_type(&field.ty, scope, namespace, node, errors)
+                            .map(|ty| (field.identifier.clone(), ty))
+                    })
+                    .collect::<Option<Vec<_>>>();
+
+                if let Some(fields) = fields {
+                    scope.set_struct(&name, fields);
+                }
+            }
+            // Constants without a type are registered by `register_inferred_constants`
+            parser::node::global::All::ConstantDeclaration { ty: None, .. } => {}
+            parser::node::global::All::Namespace {
+                identifier, body, ..
+            } => {
+                let mut namespace = namespace.to_vec();
+                namespace.push(identifier.clone());
+
+                register(body, scope, &namespace, structs, errors);
+            }
+            parser::node::global::All::Error {} => {}
+        }
+    }
+}
+
+/// A constant whose value still has to be checked for cycles, and whose type has to be inferred if
+/// it isn't declared
+struct Constant<'a> {
+    name: String,
+    identifier: &'a String,
+    namespace: Vec<String>,
+    inferred: bool,
+    value: &'a Node<parser::node::expression::All>,
+    span: span::Span,
+}
+
+/// Registers all constants without a declared type and reports constants whose value depends on
+/// itself. The type is inferred from the value, which can use other constants, so they are
+/// registered in rounds until no more can be. Constants that are left either depend on themselves
+/// or have an error in their value, which is reported here.
+fn register_inferred_constants(
+    tree: &parser::node::Global<parser::node::expression::All>,
+    scope: &mut static_analyzer::Scope,
+    errors: &mut Vec<error::Error<error::StaticAnalyzerErrorType>>,
+) {
+    let mut constants = vec![];
+    collect_constants(tree, scope, &[], &mut constants, errors);
+
+    // The indices of the constants each constant uses
+    let names = constants
+        .iter()
+        .map(|constant| constant.name.clone())
+        .collect::<Vec<_>>();
+    let uses = constants
+        .iter()
+        .map(|constant| {
+            let mut constant_scope = Scope::new(Some(scope));
+            constant_scope.set_namespace(constant.namespace.clone());
+
+            let mut used = HashSet::new();
+            used_constants(constant.value, &constant_scope, &names, &mut used);
+            used
+        })
+        .collect::<Vec<_>>();
+
+    let mut pending = vec![];
+    for (i, constant) in constants.iter().enumerate() {
+        // Follows the uses until they lead back to the constant or run out
+        let mut visited = HashSet::new();
+        let mut stack = uses[i].iter().copied().collect::<Vec<_>>();
+        while let Some(used) = stack.pop() {
+            if visited.insert(used) {
+                stack.extend(uses[used].iter().copied());
+            }
+        }
+
+        if visited.contains(&i) {
+            errors.push(error::Error::new(
+                error::StaticAnalyzerErrorType::ConstantCycle(
+                    constant.identifier.clone(),
+                    constant.namespace.clone(),
+                ),
+                constant.span,
+            ));
+        } else if constant.inferred {
+            pending.push(i);
+        }
+    }
+
+    let failed = loop {
+        let (inferred, failed): (Vec<_>, Vec<_>) = pending
+            .into_iter()
+            .map(|i| {
+                let mut constant_scope = Scope::new(Some(scope));
+                constant_scope.set_namespace(constants[i].namespace.clone());
+
+                let ty = block::check_declaration(None, constants[i].value, &constant_scope)
+                    .map(|(ty, _)| ty);
+                (ty, i)
+            })
+            .partition(|(ty, _)| ty.is_ok());
+
+        if inferred.is_empty() {
+            break failed;
+        }
+
+        for (ty, i) in inferred {
+            if let Ok(ty) = ty {
+                scope.set_variable(&constants[i].name, ty, false);
+            }
+        }
+
+        pending = failed.into_iter().map(|(_, i)| i).collect();
+    };
+
+    for (ty, i) in failed {
+        // If a constant it uses has no type the error is reported at that constant instead
+        if uses[i].iter().all(|used| scope.has_variable(&names[*used])) {
+            errors.extend(ty.err().into_iter().flatten());
+        }
+    }
+}
+
+/// Collects all constants in the tree, including the ones in nested namespaces. Constants without
+/// a declared type that are already declared are reported, the others are reported by `register`.
+fn collect_constants<'a>(
+    tree: &'a parser::node::Global<parser::node::expression::All>,
+    scope: &static_analyzer::Scope,
+    namespace: &[String],
+    constants: &mut Vec<Constant<'a>>,
+    errors: &mut Vec<error::Error<error::StaticAnalyzerErrorType>>,
+) {
+    for node in tree {
+        match &node.node {
+            parser::node::global::All::ConstantDeclaration {
+                identifier,
+                ty,
+                value,
+                ..
+            } => {
+                let name = static_analyzer::Scope::combine_ns_name(namespace, identifier);
+
+                if ty.is_none()
+                    && (scope.has_variable(&name) || constants.iter().any(|c| c.name == name))
+                {
+                    errors.push(error::Error::from_span(
+                        error::StaticAnalyzerErrorType::VariableAlreadyDeclared(identifier.clone()),
+                        node,
+                    ));
+                    continue;
+                }
+
+                constants.push(Constant {
+                    name,
+                    identifier,
+                    namespace: namespace.to_vec(),
+                    inferred: ty.is_none(),
+                    value,
+                    span: node.span(),
+                });
+            }
+            parser::node::global::All::Namespace {
+                identifier, body, ..
+            } => {
+                let mut namespace = namespace.to_vec();
+                namespace.push(identifier.clone());
+
+                collect_constants(body, scope, &namespace, constants, errors);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Adds the index of every constant in `names` that the expression uses to `used`. Names are
+/// resolved like `Scope::resolve_variable`, so a registered constant shadows one in `names` that
+/// is in an outer namespace.
+fn used_constants(
+    node: &Node<parser::node::expression::All>,
+    scope: &static_analyzer::Scope,
+    names: &[String],
+    used: &mut HashSet<usize>,
+) {
+    use parser::node::expression::{All, Arithmetic, BooleanLogic, Cmp, SingleDataUnit};
+
+    let children: Vec<&Node<All>> = match &node.node {
+        All::SingleDataUnit { value } => match value {
+            SingleDataUnit::Identifier {
+                namespace,
+                identifier,
+            } => {
+                for full_ns in scope.lookup_namespaces(namespace) {
+                    let name = static_analyzer::Scope::combine_ns_name(&full_ns, identifier);
+
+                    if let Some(i) = names.iter().position(|n| *n == name) {
+                        used.insert(i);
+                        break;
+                    }
+                    if scope.has_variable(&name) {
+                        break;
+                    }
+                }
+
+                vec![]
+            }
+            SingleDataUnit::Literal { .. } => vec![],
+            SingleDataUnit::ArrayInit { values } => values.iter().collect(),
+            SingleDataUnit::StructInit { values, .. } => values.values().collect(),
+            // The function is always an identifier, which can't be a constant
+            SingleDataUnit::FunctionCall { arguments, .. } => {
+                arguments.iter().map(|(_, arg)| arg).collect()
+            }
+            SingleDataUnit::ArrayAccess { array, index } => vec![array, index],
+            SingleDataUnit::PropertyAccess { object, .. } => vec![object],
+        },
+        All::Arithmetic { value } => match value {
+            Arithmetic::Neg { value } => vec![value],
+            Arithmetic::Add { left, right }
+            | Arithmetic::Sub { left, right }
+            | Arithmetic::Mul { left, right }
+            | Arithmetic::Div { left, right }
+            | Arithmetic::Mod { left, right }
+            | Arithmetic::Pow { left, right } => vec![left, right],
+        },
+        All::BooleanLogic { value } => match value {
+            BooleanLogic::Not { value } => vec![value],
+            BooleanLogic::Or { left, right } | BooleanLogic::And { left, right } => {
+                vec![left, right]
+            }
+        },
+        All::Cmp { value } => match value {
+            Cmp::Equal { left, right }
+            | Cmp::NotEqual { left, right }
+            | Cmp::LessThan { left, right }
+            | Cmp::LessThanOrEqual { left, right }
+            | Cmp::GreaterThan { left, right }
+            | Cmp::GreaterThanOrEqual { left, right } => vec![left, right],
+        },
+    };
+
+    for child in children {
+        used_constants(child, scope, names, used);
+    }
+}
+
+fn check_bodies(
+    tree: &parser::node::Global<parser::node::expression::All>,
+    scope: &static_analyzer::Scope,
+    namespace: &[String],
+    errors: &mut Vec<error::Error<error::StaticAnalyzerErrorType>>,
+) -> parser::node::Global<parser::node::expression::AllWithType> {
+    let mut ret_globals = vec![];
+
+    for node in tree {
+        match &node.node {
+            parser::node::global::All::FunctionDeclaration {
+                identifier,
+                arguments,
+                return_type,
+                body,
+                doc,
+            } => {
+                let mut function_scope = Scope::new(Some(scope));
+                function_scope.set_namespace(namespace.to_vec());
+
+                // Types that can't be resolved have already been reported by `register`
+                let Some(return_type) = function_scope.resolve_type(return_type) else {
+                    continue;
+                };
+                let Some(arguments) = arguments
+                    .iter()
+                    .map(|arg| {
+                        function_scope
+                            .resolve_type(&arg.ty)
+                            .map(|ty| parser::node::global::FunctionArgument { ty, ..arg.clone() })
+                    })
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    continue;
+                };
+
+                function_scope.set_return_type(return_type.clone());
+
+                for arg in &arguments {
+                    if function_scope.has_variable(&arg.identifier) {
+                        errors.push(error::Error::from_span(
+                            error::StaticAnalyzerErrorType::VariableAlreadyDeclared(
+                                arg.identifier.clone(),
+                            ),
+                            node,
+                        ));
+                    }
+
+                    function_scope.set_variable(&arg.identifier, arg.ty.clone(), true);
+                }
+
+                match block::check(body, &mut function_scope) {
+                    Ok(body)
+                        if return_type != common::Type::Empty
+                            && block::flow_type(&body) != common::Type::Never =>
+                    {
+                        errors.push(error::Error::from_span(
+                            error::StaticAnalyzerErrorType::MissingReturn(
+                                identifier.clone(),
+                                namespace.to_vec(),
+                            ),
+                            node,
+                        ));
+                    }
+                    Ok(body) => ret_globals.push(Node::from_span(
+                        parser::node::global::All::FunctionDeclaration {
+                            identifier: identifier.clone(),
+                            arguments,
+                            return_type,
+                            body,
+                            doc: doc.clone(),
+                        },
+                        node,
+                    )),
+                    Err(errs) => errors.extend(errs),
+                }
+            }
+            parser::node::global::All::ConstantDeclaration {
+                identifier,
+                ty,
+                value,
+                doc,
+            } => {
+                let mut constant_scope = Scope::new(Some(scope));
+                constant_scope.set_namespace(namespace.to_vec());
+
+                let ty = match ty {
+                    Some(ty) => match constant_scope.resolve_type(ty) {
+                        Some(ty) => Some(ty),
+                        // Already reported by `register`
+                        None => continue,
+                    },
+                    // Constants whose type couldn't be inferred have already been reported by
+                    // `register_inferred_constants`
+                    None if !constant_scope.has_variable(
+                        &static_analyzer::Scope::combine_ns_name(namespace, identifier),
+                    ) =>
+                    {
+                        continue
+                    }
+                    None => None,
+                };
+
+                let (ty, value) =
+                    match block::check_declaration(ty.as_ref(), value, &constant_scope) {
+                        Ok(checked) => checked,
+                        Err(errs) => {
+                            errors.extend(errs);
+                            continue;
+                        }
+                    };
+
+                ret_globals.push(Node::from_span(
+                    parser::node::global::All::ConstantDeclaration {
+                        identifier: identifier.clone(),
+                        ty: Some(ty),
+                        value,
+                        doc: doc.clone(),
+                    },
+                    node,
+                ));
+            }
+            parser::node::global::All::IntrinsicDeclaration {
+                identifier,
+                arguments,
+                return_type,
+                id,
+                doc,
+            } => {
+                if !scope.allow_intrinsics() {
+                    errors.push(error::Error::from_span(
+                        error::StaticAnalyzerErrorType::IntrinsicOutsideOfPrelude,
+                        node,
+                    ));
+                    continue;
+                }
+
+                let Some(intrinsic) = intrinsic::Intrinsic::from_id(id) else {
+                    errors.push(error::Error::from_span(
+                        error::StaticAnalyzerErrorType::IntrinsicNotDefined(id.clone()),
+                        node,
+                    ));
+                    continue;
+                };
+
+                let args = arguments
+                    .iter()
+                    .map(|arg| (arg.is_out, arg.ty.clone()))
+                    .collect::<Vec<_>>();
+
+                // Intrinsics only use built-in types so the types don't have to be resolved
+                if intrinsic.signature() != (args, return_type.clone()) {
+                    errors.push(error::Error::from_span(
+                        error::StaticAnalyzerErrorType::IntrinsicSignatureMismatch(id.clone()),
+                        node,
+                    ));
+                    continue;
+                }
+
+                ret_globals.push(Node::from_span(
+                    parser::node::global::All::IntrinsicDeclaration {
+                        identifier: identifier.clone(),
+                        arguments: arguments.clone(),
+                        return_type: return_type.clone(),
+                        id: id.clone(),
+                        doc: doc.clone(),
+                    },
+                    node,
+                ));
+            }
+            parser::node::global::All::ExternDeclaration {
+                identifier,
+                arguments,
+                return_type,
+                module,
+                export,
+                doc,
+            } => {
+                let mut extern_scope = Scope::new(Some(scope));
+                extern_scope.set_namespace(namespace.to_vec());
+
+                // Types that can't be resolved have already been reported by `register`
+                let Some(return_type) = extern_scope.resolve_type(return_type) else {
+                    continue;
+                };
+                let Some(arguments) = arguments
+                    .iter()
+                    .map(|arg| {
+                        extern_scope
+                            .resolve_type(&arg.ty)
+                            .map(|ty| parser::node::global::FunctionArgument { ty, ..arg.clone() })
+                    })
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    continue;
+                };
+
+                let invalid_types = std::iter::once(&return_type)
+                    .filter(|ty| **ty != common::Type::Empty)
+                    .chain(arguments.iter().map(|arg| &arg.ty))
+                    .filter(|ty| !is_extern_type(ty))
+                    .collect::<Vec<_>>();
+
+                if !invalid_types.is_empty() {
+                    errors.extend(invalid_types.into_iter().map(|ty| {
+                        error::Error::from_span(
+                            error::StaticAnalyzerErrorType::InvalidExternType(ty.clone()),
+                            node,
+                        )
+                    }));
+                    continue;
+                }
+
+                ret_globals.push(Node::from_span(
+                    parser::node::global::All::ExternDeclaration {
+                        identifier: identifier.clone(),
+                        arguments,
+                        return_type,
+                        module: module.clone(),
+                        export: export.clone(),
+                        doc: doc.clone(),
+                    },
+                    node,
+                ));
+            }
+            parser::node::global::All::StructDeclaration {
+                identifier, doc, ..
+            } => {
+                // The fields have been resolved by `register`
+                let Some(fields) = scope.get_struct(namespace, identifier) else {
+                    continue;
+                };
+
+                ret_globals.push(Node::from_span(
+                    parser::node::global::All::StructDeclaration {
+                        identifier: identifier.clone(),
+                        fields: fields
+                            .iter()
+                            .map(|(identifier, ty)| parser::node::global::StructField {
+                                identifier: identifier.clone(),
+                                ty: ty.clone(),
+                            })
+                            .collect(),
+                        doc: doc.clone(),
+                    },
+                    node,
+                ));
+            }
+            parser::node::global::All::Namespace {
+                identifier,
+                body,
+                doc,
+            } => {
+                let mut namespace = namespace.to_vec();
+                namespace.push(identifier.clone());
+
+                let body = check_bodies(body, scope, &namespace, errors);
+
+                ret_globals.push(Node::from_span(
+                    parser::node::global::All::Namespace {
+                        identifier: identifier.clone(),
+                        body,
+                        doc: doc.clone(),
+                    },
+                    node,
+                ));
+            }
+            // Reported the same way as a statement with a syntax error
+            parser::node::global::All::Error {} => {
+                errors.push(error::Error::from_span(
+                    error::StaticAnalyzerErrorType::SyntaxError,
+                    node,
+                ));
+            }
+        }
+    }
+
+    ret_globals
+}
+
+/// Resolves a type written inside of `namespace`, see `Scope::resolve_type`. Reports an error if
+/// the type doesn't exist.
+fn resolve_type<C: Spanned>(
+    ty: &common::Type,
+    scope: &static_analyzer::Scope,
+    namespace: &[String],
+    node: &C,
+    errors: &mut Vec<error::Error<error::StaticAnalyzerErrorType>>,
+) -> Option<common::Type> {
+    let mut type_scope = Scope::new(Some(scope));
+    type_scope.set_namespace(namespace.to_vec());
+
+    let resolved = type_scope.resolve_type(ty);
+
+    if resolved.is_none() {
+        errors.push(error::Error::from_span(
+            error::StaticAnalyzerErrorType::TypeNotDefined(ty.clone()),
+            node,
+        ));
+    }
+
+    resolved
+}
+
+/// Returns if values of the type can be converted to plain JavaScript values and back, which is
+/// needed to pass them to and from an extern function
+fn is_extern_type(ty: &common::Type) -> bool {
+    match ty {
+        common::Type::Integer
+        | common::Type::Float
+        | common::Type::Boolean
+        | common::Type::String => true,
+        common::Type::Array(ty) => is_extern_type(ty),
+        _ => false,
+    }
+}
@@ -4,7 +4,7 @@
 //! time. This includes type checking, variable/function usage, etc.
 //!
 //! # Examples
-//! ```
+//! ```text
 //! 3 + 4 // Ok
 //! 3 + "4" // Error
 //! ```
@@ -13,31 +13,470 @@ use crate::{parser::node::Node, *};
 
 mod expression;
 mod scope;
-use cl_ln::ClLn;
 pub use scope::Scope;
+use span::Spanned;
 mod block;
+pub mod built_in;
+mod global;
+pub mod prelude;
 
+/// Checks the tree with the standard library loaded. The returned tree contains the standard
+/// library as well, so that its functions are emitted together with the code that uses them.
 pub fn evaluate(
-    tree: &Vec<Node<parser::node::block::All<Node<parser::node::expression::All>>>>,
+    tree: &parser::node::Global<parser::node::expression::All>,
 ) -> Result<
-    Vec<Node<parser::node::block::All<Node<parser::node::expression::AllWithType>>>>,
+    parser::node::Global<parser::node::expression::AllWithType>,
     Vec<error::Error<error::StaticAnalyzerErrorType>>,
 > {
     let mut scope = Scope::new(None);
-    scope.set_function(
-        "std::test",
-        vec![
-            (false, common::Type::Integer),
-            (true, common::Type::Integer),
-        ],
-        common::Type::Boolean,
-    );
 
-    let block_eval = block::check(tree, &mut scope);
+    scope.set_allow_intrinsics(true);
+    let mut ret_globals = global::check(prelude::tree(), &mut scope)?;
+    scope.set_allow_intrinsics(false);
+
+    ret_globals.extend(global::check(tree, &mut scope)?);
+
+    Ok(ret_globals)
+}
+
+/// Checks the tree without the standard library, for freestanding builds
+pub fn evaluate_freestanding(
+    tree: &parser::node::Global<parser::node::expression::All>,
+) -> Result<
+    parser::node::Global<parser::node::expression::AllWithType>,
+    Vec<error::Error<error::StaticAnalyzerErrorType>>,
+> {
+    let mut scope = Scope::new(None);
+
+    global::check(tree, &mut scope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(code: &str) -> Result<(), Vec<error::StaticAnalyzerErrorType>> {
+        let tokens = lexer::tokenize(code, 0).0;
+        let (tree, errors) = parser::gen(&tokens);
+        assert!(errors.is_empty());
+
+        evaluate(&tree)
+            .map(|_| ())
+            .map_err(|errs| errs.iter().map(|e| e.error_type().clone()).collect())
+    }
+
+    #[test]
+    fn prelude() {
+        assert!(check(
+            "fnc main() { var a: flt = 0.0; if std::parse_str(\"2\", out a) { std::print(std::math::sqrt(a)); } }"
+        )
+        .is_ok());
+
+        let tokens = lexer::tokenize("fnc main() { std::print(1); }", 0).0;
+        let (tree, errors) = parser::gen(&tokens);
+        assert!(errors.is_empty());
+        assert!(matches!(
+            evaluate_freestanding(&tree)
+                .unwrap_err()
+                .iter()
+                .map(|e| e.error_type().clone())
+                .collect::<Vec<_>>()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::FunctionNotDefined(..)]
+        ));
 
-    if let Err(errs) = block_eval {
-        return Err(errs);
+        assert!(matches!(
+            check("intrinsic fnc print(input: str) = \"print-str\";")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::IntrinsicOutsideOfPrelude]
+        ));
     }
 
-    Ok(block_eval.unwrap())
+    #[test]
+    fn intrinsics() {
+        fn check_intrinsics(code: &str) -> Result<(), Vec<error::StaticAnalyzerErrorType>> {
+            let tokens = lexer::tokenize(code, 0).0;
+            let (tree, errors) = parser::gen(&tokens);
+            assert!(errors.is_empty());
+
+            let mut scope = Scope::new(None);
+            scope.set_allow_intrinsics(true);
+
+            global::check(&tree, &mut scope)
+                .map(|_| ())
+                .map_err(|errs| errs.iter().map(|e| e.error_type().clone()).collect())
+        }
+
+        assert!(matches!(
+            check_intrinsics("intrinsic fnc print(input: str) = \"print\";")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::IntrinsicNotDefined(..)]
+        ));
+        assert!(matches!(
+            check_intrinsics("intrinsic fnc print(input: int) = \"print-str\";")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::IntrinsicSignatureMismatch(
+                ..
+            )]
+        ));
+
+        // Every intrinsic has to be declared in the prelude
+        fn declared_ids(tree: &parser::node::Global<parser::node::expression::All>) -> Vec<String> {
+            tree.iter()
+                .flat_map(|node| match &node.node {
+                    parser::node::global::All::IntrinsicDeclaration { id, .. } => vec![id.clone()],
+                    parser::node::global::All::Namespace { body, .. } => declared_ids(body),
+                    _ => vec![],
+                })
+                .collect()
+        }
+
+        let ids = declared_ids(prelude::tree());
+        for intrinsic in intrinsic::Intrinsic::ALL {
+            assert!(ids.iter().any(|id| id == intrinsic.id()));
+        }
+    }
+
+    #[test]
+    fn extern_functions() {
+        assert!(check(
+            "ns js { extern fnc split(input: str, out output: [int]) -> bln = \"./js.js#split\"; } \
+             fnc main() { var a: [int] = []; if js::split(\"1,2\", out a) { std::print(a[0]); } }"
+        )
+        .is_ok());
+        assert!(matches!(
+            check("struct A { b: int } extern fnc f(a: A) -> int = \"./js.js#f\";")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::InvalidExternType(
+                common::Type::Struct { .. }
+            )]
+        ));
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let tokens = lexer::tokenize(
+            "fnc a() -> int { var b = 1 +; } fnc main() { var c: int = a(); d(); }",
+            0,
+        )
+        .0;
+        let (tree, errors) = parser::gen(&tokens);
+
+        assert_eq!(errors.len(), 1);
+        // The function with the syntax error isn't reported for missing a return, and the tree
+        // isn't returned since it can't be compiled
+        assert!(matches!(
+            evaluate(&tree)
+                .unwrap_err()
+                .iter()
+                .map(|e| e.error_type().clone())
+                .collect::<Vec<_>>()
+                .as_slice(),
+            [
+                error::StaticAnalyzerErrorType::SyntaxError,
+                error::StaticAnalyzerErrorType::FunctionNotDefined(..)
+            ]
+        ));
+
+        let tokens = lexer::tokenize("fnc main() { var a = 1 +; } struct", 0).0;
+        let (tree, errors) = parser::gen(&tokens);
+
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(
+            evaluate(&tree)
+                .unwrap_err()
+                .iter()
+                .map(|e| e.error_type().clone())
+                .collect::<Vec<_>>()
+                .as_slice(),
+            [
+                error::StaticAnalyzerErrorType::SyntaxError,
+                error::StaticAnalyzerErrorType::SyntaxError
+            ]
+        ));
+    }
+
+    #[test]
+    fn return_on_every_path() {
+        assert!(check("fnc a(b: bln) -> int { if b { ret 1; } else { ret 2; } }").is_ok());
+        assert!(matches!(
+            check("fnc a(b: bln) -> int { if b { ret 1; } }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::MissingReturn(..)]
+        ));
+    }
+
+    #[test]
+    fn return_type_mismatch() {
+        assert!(matches!(
+            check("fnc a() -> int { ret true; }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::TypeMismatchReturn(
+                common::Type::Integer,
+                common::Type::Boolean
+            )]
+        ));
+        assert!(matches!(
+            check("fnc a() { ret 1; }").unwrap_err().as_slice(),
+            [error::StaticAnalyzerErrorType::ReturnValueInEmptyFunction]
+        ));
+        assert!(matches!(
+            check("fnc a() -> flt { ret; }").unwrap_err().as_slice(),
+            [error::StaticAnalyzerErrorType::MissingReturnValue(
+                common::Type::Float
+            )]
+        ));
+    }
+
+    #[test]
+    fn reassign_constant() {
+        assert!(matches!(
+            check("fnc a() { cst b: int = 1; b = 2; }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::CannotAssignToConstant(..)]
+        ));
+        assert!(matches!(
+            check("fnc a() { cst b: int = 1; b += 2; }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::CannotAssignToConstant(..)]
+        ));
+    }
+
+    #[test]
+    fn constant_as_out_argument() {
+        assert!(matches!(
+            check("fnc a(out b: int) {} fnc c() { cst d: int = 1; a(out d); }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::CannotUseConstantAsOutArgument(..)]
+        ));
+    }
+
+    #[test]
+    fn namespaced_constant() {
+        assert!(check(
+            "ns geo { ns math { cst PI: flt = 3.14; fnc tau() -> flt { ret PI * 2.0; } } } \
+             fnc main() { var a: flt = geo::math::PI; }"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn constant_order() {
+        assert!(check("cst A = B; cst B = 1; fnc main() { var a: int = A; }").is_ok());
+        assert!(check(
+            "ns a { cst X = b::Y * 2.0; } ns b { cst Y = Z; cst Z = 1.5; } \
+             fnc main() { var x: flt = a::X; }"
+        )
+        .is_ok());
+
+        assert!(matches!(
+            check("cst A = B; cst B = A + 1; cst C = A;").unwrap_err().as_slice(),
+            [
+                error::StaticAnalyzerErrorType::ConstantCycle(a, _),
+                error::StaticAnalyzerErrorType::ConstantCycle(b, _),
+            ] if a == "A" && b == "B"
+        ));
+        assert!(matches!(
+            check("cst A = A;").unwrap_err().as_slice(),
+            [error::StaticAnalyzerErrorType::ConstantCycle(..)]
+        ));
+        assert!(matches!(
+            check("cst A: int = B; cst B: int = A;")
+                .unwrap_err()
+                .as_slice(),
+            [
+                error::StaticAnalyzerErrorType::ConstantCycle(..),
+                error::StaticAnalyzerErrorType::ConstantCycle(..),
+            ]
+        ));
+        // The error is only reported at the constant that has it
+        assert!(matches!(
+            check("cst A = B; cst B = 1 + \"a\";")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::TypeMismatchOp(..)]
+        ));
+    }
+
+    #[test]
+    fn struct_fields() {
+        assert!(check(
+            "ns geo { struct Point { x: flt, y: flt } } \
+             struct Line { start: geo::Point, length: flt } \
+             fnc main() { \
+                 var a: Line = Line { start: geo::Point { x: 1.0, y: 2.0 }, length: 3.0 }; \
+                 a.start.x = a.length * 2.0; \
+             }"
+        )
+        .is_ok());
+        assert!(matches!(
+            check("struct A { b: int } fnc main() { var a: A = A { b: 1 }; var c: flt = a.b; }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::TypeMismatchAssign(
+                common::Type::Float,
+                common::Type::Integer
+            )]
+        ));
+    }
+
+    #[test]
+    fn struct_init_missing_and_extra_fields() {
+        assert!(matches!(
+            check("struct A { b: int, c: int } fnc main() { var a: A = A { b: 1, d: 2 }; }")
+                .unwrap_err()
+                .as_slice(),
+            [
+                error::StaticAnalyzerErrorType::StructFieldMissing(_, c),
+                error::StaticAnalyzerErrorType::StructFieldNotDefined(_, d),
+            ] if c == "c" && d == "d"
+        ));
+        assert!(matches!(
+            check("struct A { b: int } fnc main() { var a: A = A { b: 1 }; a.c = 2; }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::StructFieldNotDefined(..)]
+        ));
+    }
+
+    #[test]
+    fn unknown_struct() {
+        assert!(matches!(
+            check("fnc a(b: B) {}").unwrap_err().as_slice(),
+            [error::StaticAnalyzerErrorType::TypeNotDefined(..)]
+        ));
+        assert!(matches!(
+            check("struct A { b: B }").unwrap_err().as_slice(),
+            [error::StaticAnalyzerErrorType::TypeNotDefined(..)]
+        ));
+        assert!(matches!(
+            check("fnc main() { var a: [Nope] = []; }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::TypeNotDefined(..)]
+        ));
+    }
+
+    #[test]
+    fn struct_arrays() {
+        assert!(check(
+            "ns geo {
+                struct P { x: int }
+                fnc mk() -> [P] { ret [P { x: 1 }]; }
+            }
+            fnc main() { var a: [geo::P] = geo::mk(); var b: int = a[0].x; }"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn duplicate_struct() {
+        // The first declaration is used, so `b` is an unknown field
+        assert!(matches!(
+            check("struct A { a: int } struct A { b: int } fnc main() { var a = A { b: 1 }; }")
+                .unwrap_err()
+                .as_slice(),
+            [
+                error::StaticAnalyzerErrorType::StructAlreadyDeclared(name, ns),
+                error::StaticAnalyzerErrorType::StructFieldMissing(..),
+                error::StaticAnalyzerErrorType::StructFieldNotDefined(..),
+            ] if name == "A" && ns.is_empty()
+        ));
+        assert!(check("struct A { a: int } ns b { struct A { a: int } }").is_ok());
+    }
+
+    #[test]
+    fn assign_to_constant_field() {
+        assert!(matches!(
+            check("struct A { b: int } fnc main() { cst a: A = A { b: 1 }; a.b = 2; }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::CannotAssignToConstant(..)]
+        ));
+    }
+
+    #[test]
+    fn arrays() {
+        assert!(check(
+            "fnc main() { \
+                 var a: [[int]] = [[1, 2], []]; \
+                 a[1] = [std::len(a[0])]; \
+                 a[0][1] += a[1][0]; \
+             }"
+        )
+        .is_ok());
+        assert!(matches!(
+            check("fnc main() { var a: [int] = [1, 2.0]; }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::TypeMismatchArrayElement(
+                common::Type::Integer,
+                common::Type::Float
+            )]
+        ));
+        assert!(matches!(
+            check("fnc main() { var a: [int] = [1]; var b: int = a[true]; }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::TypeMismatchIndex(
+                common::Type::Boolean
+            )]
+        ));
+        assert!(matches!(
+            check("fnc main() { var a: int = std::len([]); }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::CannotInferEmptyArray]
+        ));
+        assert!(matches!(
+            check("fnc main() { var a: int = std::len(1); }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::FunctionArgumentMismatch(..)]
+        ));
+    }
+
+    #[test]
+    fn inferred_types() {
+        assert!(check(
+            "ns math { cst PI = 3.14; cst TAU = PI * 2.0; } \
+             fnc main() { var a = math::TAU; var b: flt = a; var c = [[1]]; c[0][0] = 2; }"
+        )
+        .is_ok());
+        assert!(matches!(
+            check("fnc main() { var a = 1; a = 2.0; }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::TypeMismatchAssign(
+                common::Type::Integer,
+                common::Type::Float
+            )]
+        ));
+        assert!(matches!(
+            check("fnc main() { var a: int = 1.0; }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::TypeMismatchAssign(
+                common::Type::Integer,
+                common::Type::Float
+            )]
+        ));
+        assert!(matches!(
+            check("fnc a() {} fnc main() { var b = a(); }")
+                .unwrap_err()
+                .as_slice(),
+            [error::StaticAnalyzerErrorType::InvalidVariableType(
+                common::Type::Empty
+            )]
+        ));
+    }
 }
@@ -0,0 +1,32 @@
+//! The standard library, written in zyrahn and loaded before the code that is compiled. Most of
+//! its functions are declared as intrinsics, see `intrinsic`, which is only allowed here.
+//!
+//! # Examples
+//! ```text
+//! std::print("Hello, world!");
+//! std::math::sqrt(2.0);
+//! ```
+use crate::*;
+use std::sync::OnceLock;
+
+pub const SOURCE: &str = include_str!("../std.zy");
+
+/// The file id of the standard library, it's always the first file in a `span::SourceMap`, even if
+/// the code is freestanding
+pub const FILE_ID: span::FileId = 0;
+
+/// Returns the syntax tree of the standard library, it's only parsed the first time this is
+/// called
+pub fn tree() -> &'static parser::node::Global<parser::node::expression::All> {
+    static TREE: OnceLock<parser::node::Global<parser::node::expression::All>> = OnceLock::new();
+
+    TREE.get_or_init(|| {
+        let (tokens, errors) = lexer::tokenize(SOURCE, FILE_ID);
+        assert!(errors.is_empty(), "std.zy failed to tokenize");
+
+        let (tree, errors) = parser::gen(&tokens);
+        assert!(errors.is_empty(), "std.zy failed to parse");
+
+        tree
+    })
+}
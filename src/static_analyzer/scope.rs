@@ -1,15 +1,38 @@
 use crate::*;
 use std::collections::HashMap;
 
+/// The argument types of a function overload together with its return type
+type FunctionOverload = (Vec<(bool, common::Type)>, common::Type);
+
+/// The fields of a struct in the order they were declared
+pub type StructFields = Vec<(String, common::Type)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    pub ty: common::Type,
+    // Constants declared with `cst` can't be reassigned or used as out arguments
+    pub mutable: bool,
+}
+
 pub struct Scope<'a> {
     // This way of doing it might be quite inefficient. Since only variables are allowed in lower
     // scopes. So get_function for example will need to be called recursively until it reaches the
     // top scope.
     parent: Option<&'a Scope<'a>>,
-    variables: HashMap<String, common::Type>,
+    variables: HashMap<String, Variable>,
     // Why is the look up for functions a double vector?
     // First vector is function overloads, second vector is the types of the arguments.
-    functions: HashMap<String, Vec<(Vec<(bool, common::Type)>, common::Type)>>,
+    functions: HashMap<String, Vec<FunctionOverload>>,
+    // Structs are like functions only declared in the root scope, keyed by their absolute name
+    structs: HashMap<String, StructFields>,
+    // The namespace the code in this scope is written in, used to resolve unqualified names
+    namespace: Vec<String>,
+    // If the code in this scope is inside a loop, i.e. if `break` and `continue` can be used
+    in_loop: bool,
+    // The return type of the function the code in this scope belongs to
+    return_type: Option<common::Type>,
+    // If intrinsics can be declared, which is only the case in the prelude
+    allow_intrinsics: bool,
 }
 
 impl<'a> Scope<'a> {
@@ -18,20 +41,84 @@ impl<'a> Scope<'a> {
             parent,
             variables: HashMap::new(),
             functions: HashMap::new(),
+            structs: HashMap::new(),
+            namespace: parent.map_or(vec![], |parent| parent.namespace.clone()),
+            in_loop: parent.is_some_and(|parent| parent.in_loop),
+            return_type: parent.and_then(|parent| parent.return_type.clone()),
+            allow_intrinsics: parent.is_some_and(|parent| parent.allow_intrinsics),
         }
     }
 
-    pub fn get_function<'b>(
+    pub fn return_type(&self) -> Option<common::Type> {
+        self.return_type.clone()
+    }
+
+    pub fn set_return_type(&mut self, return_type: common::Type) {
+        self.return_type = Some(return_type);
+    }
+
+    pub fn in_loop(&self) -> bool {
+        self.in_loop
+    }
+
+    pub fn set_in_loop(&mut self, in_loop: bool) {
+        self.in_loop = in_loop;
+    }
+
+    pub fn allow_intrinsics(&self) -> bool {
+        self.allow_intrinsics
+    }
+
+    pub fn set_allow_intrinsics(&mut self, allow_intrinsics: bool) {
+        self.allow_intrinsics = allow_intrinsics;
+    }
+
+    pub fn namespace(&self) -> &[String] {
+        &self.namespace
+    }
+
+    pub fn set_namespace(&mut self, namespace: Vec<String>) {
+        self.namespace = namespace;
+    }
+
+    /// Returns every absolute namespace a name written as `ns::name` could refer to, starting with
+    /// the innermost enclosing namespace and ending with the root namespace.
+    ///
+    /// # Example
+    /// Inside `ns std { ns math { ... } }` the name `a::b` could refer to `std::math::a::b`,
+    /// `std::a::b` or `a::b`.
+    pub fn lookup_namespaces(&self, ns: &[String]) -> Vec<Vec<String>> {
+        (0..=self.namespace.len())
+            .rev()
+            .map(|i| [&self.namespace[..i], ns].concat())
+            .collect()
+    }
+
+    /// Same as `get_function` but resolves the name against the enclosing namespaces. Returns the
+    /// absolute namespace the function was found in together with its return type.
+    pub fn resolve_function(
         &self,
-        ns: &Vec<String>,
+        ns: &[String],
         name: &str,
-        args: &Vec<(bool, common::Type)>,
+        args: &[(bool, common::Type)],
+    ) -> Option<(Vec<String>, common::Type)> {
+        self.lookup_namespaces(ns).into_iter().find_map(|full_ns| {
+            self.get_function(&full_ns, name, args)
+                .map(|ret_type| (full_ns, ret_type))
+        })
+    }
+
+    pub fn get_function(
+        &self,
+        ns: &[String],
+        name: &str,
+        args: &[(bool, common::Type)],
     ) -> Option<common::Type> {
         let name_with_ns = Scope::combine_ns_name(ns, name);
 
         self.functions
             .get(&name_with_ns)
-            .map_or(None, |overloads| {
+            .and_then(|overloads| {
                 overloads
                     .iter()
                     .find(|(overload_args, _)| {
@@ -42,14 +129,11 @@ impl<'a> Scope<'a> {
                         }
                     })
                     .map(|(_, ret_type)| ret_type)
-                    .clone()
             })
             .cloned()
-            .or_else(|| {
-                self.parent
-                    .as_ref()
-                    .and_then(|parent| parent.get_function(ns, name, args))
-                    .map_or(None, |ret_type| Some(ret_type.clone()))
+            .or_else(|| match self.parent {
+                Some(parent) => parent.get_function(ns, name, args),
+                None => static_analyzer::built_in::get_function(ns, name, args),
             })
     }
 
@@ -61,21 +145,21 @@ impl<'a> Scope<'a> {
     ) {
         self.functions
             .entry(name.to_string())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push((args, ret_type));
     }
 
-    pub fn has_function(&self, ns: &Vec<String>, name: &str) -> bool {
+    pub fn has_function(&self, ns: &[String], name: &str) -> bool {
         let name_with_ns = Scope::combine_ns_name(ns, name);
 
         self.functions.contains_key(&name_with_ns)
-            || self
-                .parent
-                .as_ref()
-                .map_or(false, |parent| parent.has_function(ns, name))
+            || match self.parent {
+                Some(parent) => parent.has_function(ns, name),
+                None => static_analyzer::built_in::has_function(ns, name),
+            }
     }
 
-    pub fn get_variable(&self, ns: &Vec<String>, name: &str) -> Option<&common::Type> {
+    pub fn get_variable(&self, ns: &[String], name: &str) -> Option<&Variable> {
         let name_with_ns = Scope::combine_ns_name(ns, name);
 
         self.variables.get(&name_with_ns).or_else(|| {
@@ -90,18 +174,119 @@ impl<'a> Scope<'a> {
             || self
                 .parent
                 .as_ref()
-                .map_or(false, |parent| parent.has_variable(name))
+                .is_some_and(|parent| parent.has_variable(name))
     }
 
-    pub fn set_variable(&mut self, name: &str, value: common::Type) {
-        self.variables.insert(name.to_string(), value);
+    pub fn set_variable(&mut self, name: &str, ty: common::Type, mutable: bool) {
+        self.variables
+            .insert(name.to_string(), Variable { ty, mutable });
     }
 
-    pub fn combine_ns_name(ns: &Vec<String>, name: &str) -> String {
-        if ns.len() != 0 {
+    /// Resolves a variable the same way as `resolve_function`. Local variables always take
+    /// precedence over constants declared in a namespace.
+    pub fn resolve_variable(&self, ns: &[String], name: &str) -> Option<(Vec<String>, &Variable)> {
+        if ns.is_empty() {
+            if let Some(variable) = self.get_local_variable(name) {
+                return Some((vec![], variable));
+            }
+        }
+
+        self.lookup_namespaces(ns).into_iter().find_map(|full_ns| {
+            self.get_variable(&full_ns, name)
+                .map(|variable| (full_ns, variable))
+        })
+    }
+
+    /// Gets a variable declared inside a function, i.e. in any scope except the root scope
+    fn get_local_variable(&self, name: &str) -> Option<&Variable> {
+        let parent = self.parent?;
+
+        self.variables
+            .get(name)
+            .or_else(|| parent.get_local_variable(name))
+    }
+
+    pub fn get_struct(&self, ns: &[String], name: &str) -> Option<&StructFields> {
+        let name_with_ns = Scope::combine_ns_name(ns, name);
+
+        self.structs.get(&name_with_ns).or_else(|| {
+            self.parent
+                .as_ref()
+                .and_then(|parent| parent.get_struct(ns, name))
+        })
+    }
+
+    pub fn set_struct(&mut self, name: &str, fields: StructFields) {
+        self.structs.insert(name.to_string(), fields);
+    }
+
+    /// Resolves a struct the same way as `resolve_function`. Returns the absolute namespace the
+    /// struct was found in together with its fields.
+    pub fn resolve_struct(
+        &self,
+        ns: &[String],
+        name: &str,
+    ) -> Option<(Vec<String>, &StructFields)> {
+        self.lookup_namespaces(ns).into_iter().find_map(|full_ns| {
+            self.get_struct(&full_ns, name)
+                .map(|fields| (full_ns, fields))
+        })
+    }
+
+    /// Turns a type written in the code into the type it refers to, i.e. gives struct types their
+    /// absolute namespace, including the elements of arrays. Returns `None` if the type refers to
+    /// a struct that doesn't exist.
+    pub fn resolve_type(&self, ty: &common::Type) -> Option<common::Type> {
+        match ty {
+            common::Type::Array(element_ty) => self
+                .resolve_type(element_ty)
+                .map(|element_ty| common::Type::Array(Box::new(element_ty))),
+            common::Type::Struct {
+                namespace,
+                identifier,
+            } => self
+                .resolve_struct(namespace, identifier)
+                .map(|(namespace, _)| common::Type::Struct {
+                    namespace,
+                    identifier: identifier.clone(),
+                }),
+            ty => Some(ty.clone()),
+        }
+    }
+
+    pub fn combine_ns_name(ns: &[String], name: &str) -> String {
+        if !ns.is_empty() {
             format!("{}::{}", ns.join("::"), name)
         } else {
             name.to_string()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_enclosing_namespace_first() {
+        let mut root = Scope::new(None);
+        root.set_function("b", vec![], common::Type::Integer);
+        root.set_function("std::b", vec![], common::Type::Float);
+
+        let mut scope = Scope::new(Some(&root));
+        scope.set_namespace(vec!["std".to_string(), "math".to_string()]);
+
+        assert_eq!(
+            scope.resolve_function(&[], "b", &[]),
+            Some((vec!["std".to_string()], common::Type::Float))
+        );
+        assert_eq!(
+            root.resolve_function(&[], "b", &[]),
+            Some((vec![], common::Type::Integer))
+        );
+        assert_eq!(
+            scope.resolve_function(&["math".to_string()], "b", &[]),
+            None
+        );
+    }
+}
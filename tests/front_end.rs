@@ -0,0 +1,237 @@
+//! Feeds arbitrary input through the lexer, the parser and the static analyzer, and compiles it if
+//! it's valid. Whatever the input is, every stage has to report errors instead of panicking.
+
+use proptest::prelude::*;
+use zyrahn::{compiler, lexer, parser, span, static_analyzer};
+
+/// Pieces of code that are combined into random programs. Random bytes are almost never valid
+/// tokens, so these get much further into the parser and the static analyzer.
+const FRAGMENTS: &[&str] = &[
+    "fnc",
+    "cst",
+    "var",
+    "if",
+    "else",
+    "ret",
+    "break",
+    "continue",
+    "while",
+    "struct",
+    "int",
+    "flt",
+    "bln",
+    "str",
+    "true",
+    "false",
+    "out",
+    "ns",
+    "intrinsic",
+    "extern",
+    "main",
+    "a",
+    "b",
+    "std",
+    "print",
+    "Point",
+    "x",
+    "0",
+    "1",
+    "2.5",
+    "0xFF",
+    "\"s\"",
+    "r\"raw\"",
+    "///",
+    "\n",
+    "(",
+    ")",
+    "{",
+    "}",
+    "[",
+    "]",
+    "->",
+    ";",
+    ":",
+    "::",
+    ",",
+    ".",
+    "=",
+    "+=",
+    "-=",
+    "**=",
+    "+",
+    "-",
+    "*",
+    "**",
+    "/",
+    "%",
+    "==",
+    "!=",
+    "<",
+    "<=",
+    ">",
+    ">=",
+    "||",
+    "&&",
+    "!",
+    "$",
+    "\"",
+    "/*",
+    "*/",
+];
+
+/// Token types for generating token sequences that the lexer would never produce, e.g. a doc
+/// comment in the middle of an expression
+fn token_type() -> impl Strategy<Value = lexer::TokenType> {
+    use lexer::TokenType::*;
+
+    prop_oneof![
+        prop::sample::select(vec![
+            Function,
+            Const,
+            Var,
+            If,
+            Else,
+            Return,
+            Break,
+            Continue,
+            While,
+            Struct,
+            Integer,
+            Float,
+            Boolean,
+            String,
+            Out,
+            Namespace,
+            Intrinsic,
+            Extern,
+            Add,
+            Sub,
+            Mul,
+            Pow,
+            Mod,
+            Div,
+            ParenOpen,
+            ParenClose,
+            CurlyOpen,
+            CurlyClose,
+            SquareOpen,
+            SquareClose,
+            RightArrow,
+            Semicolon,
+            DoubleColon,
+            Colon,
+            Comma,
+            Dot,
+            Assign,
+            AddAssign,
+            PowAssign,
+            Equal,
+            NotEqual,
+            LessThan,
+            GreaterThanOrEqual,
+            Or,
+            And,
+            Not,
+            Error,
+        ]),
+        "[a-z]{1,3}".prop_map(Identifier),
+        any::<i64>().prop_map(IntegerLiteral),
+        any::<f64>().prop_map(FloatLiteral),
+        "[a-z]{0,3}".prop_map(StringLiteral),
+        "[a-z]{0,3}".prop_map(DocComment),
+        any::<bool>().prop_map(BooleanLiteral),
+    ]
+}
+
+/// Runs the front end on tokens, the results don't matter as long as nothing panics. The errors
+/// are formatted too, since that's what happens to them when they are reported. A tree the static
+/// analyzer accepts is compiled.
+fn check_tokens(tokens: &[lexer::Token], source_map: &span::SourceMap) {
+    let (tree, errors) = parser::gen(tokens);
+    errors.iter().for_each(|e| drop(e.to_string()));
+
+    for result in [
+        static_analyzer::evaluate(&tree),
+        static_analyzer::evaluate_freestanding(&tree),
+    ] {
+        match result {
+            Ok(typed_tree) => drop(compiler::javascript::compile(&typed_tree, source_map)),
+            Err(errors) => errors.iter().for_each(|e| drop(e.to_string())),
+        }
+    }
+}
+
+fn check_code(code: &str) {
+    let mut source_map = span::SourceMap::new();
+    let file_id = source_map.add_file("main.zy", code);
+
+    let (tokens, errors) = lexer::tokenize(code, file_id);
+    errors.iter().for_each(|e| drop(e.to_string()));
+
+    check_tokens(&tokens, &source_map);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1000))]
+
+    #[test]
+    fn random_bytes(bytes in prop::collection::vec(any::<u8>(), 0..200)) {
+        check_code(&String::from_utf8_lossy(&bytes));
+    }
+
+    #[test]
+    fn random_fragments(
+        fragments in prop::collection::vec(prop::sample::select(FRAGMENTS), 0..60),
+    ) {
+        check_code(&fragments.join(" "));
+    }
+
+    #[test]
+    fn random_programs(
+        body in prop::collection::vec(prop::sample::select(FRAGMENTS), 0..30),
+    ) {
+        // Wrapped in a function so that more of the code reaches the block parser and the static
+        // analyzer
+        check_code(&format!("fnc main() {{ {} }}", body.join(" ")));
+    }
+
+    #[test]
+    fn random_tokens(token_types in prop::collection::vec(token_type(), 0..60)) {
+        let tokens = token_types
+            .into_iter()
+            .enumerate()
+            .map(|(i, token_type)| lexer::Token {
+                token_type,
+                span: span::Span::new(1, i, i + 1),
+            })
+            .collect::<Vec<_>>();
+
+        // Every token is a single character of the file
+        let mut source_map = span::SourceMap::new();
+        source_map.add_file("main.zy", &" ".repeat(tokens.len()));
+
+        check_tokens(&tokens, &source_map);
+    }
+}
+
+proptest! {
+    // Every case is thousands of tokens long, so fewer are run
+    #![proptest_config(ProptestConfig::with_cases(100))]
+
+    #[test]
+    fn deep_types(
+        depth in 0..5000usize,
+        template in prop::sample::select(&[
+            "fnc main() { var a: TYPE = 1; }",
+            "fnc main() { cst a: TYPE = 1; }",
+            "cst a: TYPE = 1;",
+            "fnc f(a: TYPE) {}",
+            "fnc f() -> TYPE {}",
+            "struct S { a: TYPE }",
+        ][..]),
+    ) {
+        // Array types are parsed recursively, so they have to be as limited as blocks are
+        let ty = format!("{}int{}", "[".repeat(depth), "]".repeat(depth));
+        check_code(&template.replace("TYPE", &ty));
+    }
+}